@@ -0,0 +1,85 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use sxd_html_table::{
+    extract_table_nodes_to_table, extract_table_nodes_to_table_lenient, ExtractOptions,
+};
+
+/// A plain `rows` × `cols` table.
+fn plain_table(rows: usize, cols: usize) -> String {
+    let mut html = String::from("<table>");
+    for row in 0..rows {
+        html.push_str("<tr>");
+        for col in 0..cols {
+            html.push_str(&format!("<td>{row}-{col}</td>"));
+        }
+        html.push_str("</tr>");
+    }
+    html.push_str("</table>");
+    html
+}
+
+/// Rows alternating between wide colspans and cells that land in the gaps
+/// left by tall rowspans.
+fn spanning_table(rows: usize) -> String {
+    let mut html = String::from("<table>");
+    for row in 0..rows {
+        html.push_str("<tr>");
+        if row % 4 == 0 {
+            html.push_str(
+                r#"<td rowspan="4">r</td><td colspan="50">wide</td><td colspan="49">wide</td>"#,
+            );
+        } else {
+            for col in 0..20 {
+                html.push_str(&format!(r#"<td colspan="5">{col}</td>"#));
+            }
+        }
+        html.push_str("</tr>");
+    }
+    html.push_str("</table>");
+    html
+}
+
+/// Two-column rows whose first cell has a non-numeric colspan, each reported
+/// as a diagnostic by lenient extraction.
+fn invalid_spans_table(rows: usize) -> String {
+    let row = r#"<tr><td colspan="x">a</td><td>b</td></tr>"#;
+    format!("<table>{}</table>", row.repeat(rows))
+}
+
+fn bench_extract(c: &mut Criterion) {
+    let mut group = c.benchmark_group("extract");
+    group.sample_size(10);
+    let inputs = [
+        ("plain 10000x10", plain_table(10_000, 10)),
+        ("plain 1000x100", plain_table(1_000, 100)),
+        ("colspans 2000x100", spanning_table(2_000)),
+        ("invalid colspans 50000x2", invalid_spans_table(50_000)),
+    ];
+    for (name, html) in &inputs {
+        let package = sxd_html::parse_html(html);
+        let document = package.as_document();
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            &document,
+            |b, document| {
+                b.iter(|| extract_table_nodes_to_table(black_box(document.root())));
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_extract_lenient(c: &mut Criterion) {
+    let mut group = c.benchmark_group("extract_lenient");
+    group.sample_size(10);
+    let html = invalid_spans_table(50_000);
+    let package = sxd_html::parse_html(&html);
+    let document = package.as_document();
+    let options = ExtractOptions::new();
+    group.bench_function("invalid colspans 50000x2", |b| {
+        b.iter(|| extract_table_nodes_to_table_lenient(black_box(document.root()), &options));
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_extract, bench_extract_lenient);
+criterion_main!(benches);
@@ -1,9 +1,35 @@
+use crate::table::Alignment;
+
+/// Namespace URI of XHTML elements.
+pub const XHTML_NAMESPACE: &str = "http://www.w3.org/1999/xhtml";
+
+/// Returns `true` if `element` is the HTML element named `local_name`.
+///
+/// Elements match both without a namespace, as produced by `sxd_html`, and in
+/// the XHTML namespace under any prefix, as produced by parsing XHTML with
+/// `sxd_document::parser`.
+pub fn is_html_element(element: sxd_document::dom::Element, local_name: &str) -> bool {
+    let name = element.name();
+    name.local_part() == local_name && matches!(name.namespace_uri(), None | Some(XHTML_NAMESPACE))
+}
+
+/// Largest `colspan` (and `<col span>`) honored by the HTML table model.
+pub const MAX_COLSPAN: usize = 1000;
+
+/// Largest `rowspan` honored by the HTML table model.
+pub const MAX_ROWSPAN: usize = 65534;
+
 /// Returns `(rowspan, colspan)` for an HTML element.
 ///
 /// The first tuple element is the row span and the second is the column span.
-/// Missing or non-numeric attributes default to `1`.
+/// Values are read with the HTML rules for parsing non-negative integers, so
+/// `" 2"`, `"2px"` and `"+2"` all mean `2`; missing attributes and values
+/// that do not start with a number default to `1`. As in the HTML table
+/// model, `colspan="0"` counts as `1`, `colspan` is capped at
+/// [`MAX_COLSPAN`] and `rowspan` at [`MAX_ROWSPAN`]. `rowspan="0"` is
+/// returned as `0`, meaning the cell extends to the end of its row group.
 pub fn extract_rowspan_and_colspan(element: sxd_document::dom::Element) -> (usize, usize) {
-    let rowspan = extract_span(element, "rowspan");
+    let rowspan = extract_span(element, "rowspan").min(MAX_ROWSPAN);
     let colspan = extract_colspan(element);
     (rowspan, colspan)
 }
@@ -11,11 +37,115 @@ pub fn extract_rowspan_and_colspan(element: sxd_document::dom::Element) -> (usiz
 fn extract_span(element: sxd_document::dom::Element, name: &str) -> usize {
     element
         .attribute_value(name)
-        .unwrap_or("1")
-        .parse::<usize>()
+        .and_then(parse_span)
         .unwrap_or(1)
 }
 
+/// Parses a `rowspan`, `colspan` or `span` attribute value with the HTML
+/// [rules for parsing non-negative integers], returning `None` on error.
+///
+/// Leading ASCII whitespace and a `+` sign are skipped and parsing stops at
+/// the first character that is not a digit. Values too large for `usize`
+/// saturate, since callers clamp them anyway.
+///
+/// [rules for parsing non-negative integers]: https://html.spec.whatwg.org/multipage/common-microsyntaxes.html#rules-for-parsing-non-negative-integers
+pub(crate) fn parse_span(value: &str) -> Option<usize> {
+    let value = value.trim_start_matches(['\t', '\n', '\x0C', '\r', ' ']);
+    let (negative, digits) = match value.as_bytes().first() {
+        Some(b'-') => (true, &value[1..]),
+        Some(b'+') => (false, &value[1..]),
+        _ => (false, value),
+    };
+    let digits = digits.as_bytes();
+    let len = digits
+        .iter()
+        .take_while(|byte| byte.is_ascii_digit())
+        .count();
+    if len == 0 {
+        return None;
+    }
+    let span = digits[..len].iter().fold(0usize, |span, digit| {
+        span.saturating_mul(10)
+            .saturating_add(usize::from(digit - b'0'))
+    });
+    if negative && span > 0 {
+        return None;
+    }
+    Some(span)
+}
+
+/// Returns `true` if `value` is a valid non-negative integer in the sense of
+/// the HTML specification: one or more ASCII digits and nothing else.
+pub(crate) fn is_non_negative_integer(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_digit())
+}
+
 fn extract_colspan(element: sxd_document::dom::Element) -> usize {
-    extract_span(element, "colspan").max(1)
+    extract_span(element, "colspan").clamp(1, MAX_COLSPAN)
+}
+
+/// Returns the number of columns a `<col>` or `<colgroup>` element spans.
+///
+/// The value is parsed like `colspan`, see [`extract_rowspan_and_colspan`].
+/// Missing, non-numeric or zero `span` attributes count as `1`, and larger
+/// values are capped at [`MAX_COLSPAN`].
+pub fn extract_col_span(element: sxd_document::dom::Element) -> usize {
+    extract_span(element, "span").clamp(1, MAX_COLSPAN)
+}
+
+/// Returns the whitespace-separated names in the `class` attribute.
+pub fn extract_classes(element: sxd_document::dom::Element) -> Vec<String> {
+    element
+        .attribute_value("class")
+        .map(|class| class.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Returns the horizontal alignment declared on an element.
+///
+/// A `text-align` declaration in the `style` attribute takes precedence over
+/// the legacy `align` attribute. `start`/`end` are treated as left/right, and
+/// values without a Markdown equivalent such as `justify` are ignored.
+pub fn extract_text_align(element: sxd_document::dom::Element) -> Option<Alignment> {
+    let from_style = style_property(element, "text-align").and_then(parse_alignment);
+    from_style.or_else(|| element.attribute_value("align").and_then(parse_alignment))
+}
+
+/// Returns `true` if an element is hidden, either with the `hidden`
+/// attribute or with `visibility: collapse`, `visibility: hidden` or
+/// `display: none` in its `style` attribute.
+///
+/// On `<col>` and `<colgroup>` elements this hides the columns they cover.
+pub fn is_hidden(element: sxd_document::dom::Element) -> bool {
+    let has_value = |property, values: &[&str]| {
+        style_property(element, property)
+            .is_some_and(|value| values.iter().any(|v| value.eq_ignore_ascii_case(v)))
+    };
+    element.attribute_value("hidden").is_some()
+        || has_value("visibility", &["collapse", "hidden"])
+        || has_value("display", &["none"])
+}
+
+/// Returns the trimmed value of the last declaration of `property` in the
+/// `style` attribute.
+fn style_property<'d>(element: sxd_document::dom::Element<'d>, property: &str) -> Option<&'d str> {
+    element
+        .attribute_value("style")?
+        .split(';')
+        .rev()
+        .find_map(|declaration| {
+            let (name, value) = declaration.split_once(':')?;
+            name.trim()
+                .eq_ignore_ascii_case(property)
+                .then(|| value.trim())
+        })
+}
+
+fn parse_alignment(value: &str) -> Option<Alignment> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "left" | "start" => Some(Alignment::Left),
+        "center" => Some(Alignment::Center),
+        "right" | "end" => Some(Alignment::Right),
+        _ => None,
+    }
 }
@@ -0,0 +1,532 @@
+use std::fmt;
+
+use sxd_xpath::nodeset::Node;
+
+use crate::{table::Table, Limit, Location};
+
+/// What to do with a table that exceeds one of the [`ExtractOptions`]
+/// limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum LimitAction {
+    /// Fail with [`Error::LimitExceeded`](crate::Error::LimitExceeded).
+    #[default]
+    Error,
+    /// Keep the part of the table that fits: cells past the column limit
+    /// and rows past the row or cell limit are dropped, and spans are
+    /// shortened to fit. Tables past the table limit are dropped.
+    Truncate,
+    /// Leave the offending table out of the result. Tables past the table
+    /// limit are dropped.
+    Skip,
+}
+
+/// Limits applied while extracting tables, guarding against documents whose
+/// spans would otherwise make a table exhaust memory.
+///
+/// Only the number of columns is limited by default, as it always was.
+/// Set the other limits when extracting from untrusted documents.
+///
+/// ```
+/// use sxd_html_table::{extract::LimitAction, extract_table_nodes_to_table_with, ExtractOptions};
+///
+/// let package = sxd_html::parse_html(
+///     r#"<table><tr><td colspan="5">wide</td></tr><tr><td>a</td></tr></table>"#,
+/// );
+/// let options = ExtractOptions::new()
+///     .max_columns(2)
+///     .on_limit(LimitAction::Truncate);
+/// let tables = extract_table_nodes_to_table_with(package.as_document().root(), &options).unwrap();
+/// assert_eq!(tables[0].to_string_table().to_csv().unwrap(), "wide,wide\na,\n");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractOptions {
+    pub(crate) max_columns: usize,
+    pub(crate) max_rows: usize,
+    pub(crate) max_cells: usize,
+    pub(crate) max_span_area: usize,
+    pub(crate) max_tables: usize,
+    pub(crate) on_limit: LimitAction,
+    pub(crate) strict: bool,
+    pub(crate) clamp_rowspans: bool,
+}
+
+impl Default for ExtractOptions {
+    fn default() -> Self {
+        Self {
+            max_columns: 1000,
+            max_rows: usize::MAX,
+            max_cells: usize::MAX,
+            max_span_area: usize::MAX,
+            max_tables: usize::MAX,
+            on_limit: LimitAction::Error,
+            strict: false,
+            clamp_rowspans: true,
+        }
+    }
+}
+
+impl ExtractOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the largest number of grid columns of a table. Defaults to
+    /// 1000.
+    pub fn max_columns(mut self, max_columns: usize) -> Self {
+        self.max_columns = max_columns;
+        self
+    }
+
+    /// Sets the largest number of grid rows of a table, counting the rows
+    /// added by `rowspan`. Unlimited by default.
+    pub fn max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = max_rows;
+        self
+    }
+
+    /// Sets the largest number of slots, rows × columns, of a table.
+    /// Unlimited by default.
+    pub fn max_cells(mut self, max_cells: usize) -> Self {
+        self.max_cells = max_cells;
+        self
+    }
+
+    /// Sets the largest `rowspan` × `colspan` of a single cell, checked
+    /// after rowspans are clamped. A `rowspan="0"` cell is checked as it
+    /// grows. Unlimited by default.
+    pub fn max_span_area(mut self, max_span_area: usize) -> Self {
+        self.max_span_area = max_span_area;
+        self
+    }
+
+    /// Sets the largest number of top-level tables extracted from a
+    /// document. Unlimited by default.
+    pub fn max_tables(mut self, max_tables: usize) -> Self {
+        self.max_tables = max_tables;
+        self
+    }
+
+    /// Sets what happens to a table exceeding a limit. Defaults to
+    /// [`LimitAction::Error`].
+    pub fn on_limit(mut self, on_limit: LimitAction) -> Self {
+        self.on_limit = on_limit;
+        self
+    }
+
+    /// Shortens rowspans reaching past the last `<tr>` of their row group
+    /// to end there, as browsers render them, before any limit is checked.
+    /// Enabled by default.
+    ///
+    /// When disabled, such rowspans add rows to the row group as in the HTML
+    /// table-forming algorithm, so a single `rowspan="65534"` makes a table
+    /// 65534 rows tall.
+    pub fn clamp_rowspans(mut self, clamp_rowspans: bool) -> Self {
+        self.clamp_rowspans = clamp_rowspans;
+        self
+    }
+
+    /// Validates every table against the HTML table model: span attributes
+    /// that are not valid non-negative integers or out of range, overlapping
+    /// cells, rows with uncovered slots and rowspans crossing the end of
+    /// their row group.
+    ///
+    /// Extraction fails with [`Error::Validation`] on any finding, while
+    /// lenient extraction reports them as diagnostics. Disabled by default.
+    ///
+    /// [`Error::Validation`]: crate::Error::Validation
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+}
+
+/// Something repaired, left out or, in [strict](ExtractOptions::strict)
+/// mode, found non-conforming while extracting tables, see
+/// [`extract_table_nodes_to_table_lenient`] and [`validate_tables`].
+///
+/// [`extract_table_nodes_to_table_lenient`]: crate::extract_table_nodes_to_table_lenient
+/// [`validate_tables`]: crate::validate_tables
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub location: Location,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            DiagnosticKind::SpanClamped { attribute, clamped } => {
+                write!(f, "{attribute} clamped to {clamped}")?;
+            }
+            DiagnosticKind::InvalidSpan { attribute } => {
+                write!(f, "non-numeric {attribute} ignored")?;
+            }
+            DiagnosticKind::Truncated { limit, max, actual } => {
+                write!(f, "truncated to the {limit} limit: {actual} > {max}")?;
+            }
+            DiagnosticKind::TableSkipped { limit, max, actual } => {
+                write!(f, "table skipped, {limit} limit exceeded: {actual} > {max}")?;
+            }
+            DiagnosticKind::NonConformingSpan { attribute } => {
+                write!(f, "{attribute} is not a valid non-negative integer")?;
+            }
+            DiagnosticKind::OverlappingCells { other } => {
+                write!(f, "cell overlaps the cell at {other}")?;
+            }
+            DiagnosticKind::RaggedRow { covered, width } => {
+                write!(f, "row covers {covered} of {width} columns")?;
+            }
+            DiagnosticKind::RowspanCrossesRowGroup {
+                rowspan,
+                rows_in_group,
+            } => {
+                let excess = rowspan - rows_in_group;
+                let rows = if excess == 1 { "row" } else { "rows" };
+                write!(
+                    f,
+                    "rowspan {rowspan} reaches {excess} {rows} past the end of its row group"
+                )?;
+            }
+        }
+        if self.location != Location::default() {
+            write!(f, " ({})", self.location)?;
+        }
+        Ok(())
+    }
+}
+
+/// The kind of a [`Diagnostic`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DiagnosticKind {
+    /// A `rowspan` or `colspan` outside the range honored by the HTML table
+    /// model was clamped, such as `colspan="0"` to 1 or `rowspan="70000"`
+    /// to [`MAX_ROWSPAN`](crate::element_utils::MAX_ROWSPAN).
+    SpanClamped {
+        attribute: &'static str,
+        clamped: usize,
+    },
+    /// A `rowspan` or `colspan` that does not start with a number, or is
+    /// negative, was ignored, so the cell spans one row or column.
+    InvalidSpan { attribute: &'static str },
+    /// A table exceeding a limit was truncated to fit, dropping the cells,
+    /// rows or tables past it.
+    Truncated {
+        limit: Limit,
+        max: usize,
+        actual: usize,
+    },
+    /// A table exceeding a limit was left out.
+    TableSkipped {
+        limit: Limit,
+        max: usize,
+        actual: usize,
+    },
+    /// A `rowspan` or `colspan` that a browser reads as a number but which
+    /// is not written as one, such as `" 2"`, `2px` or `+2`. Strict mode
+    /// only.
+    NonConformingSpan { attribute: &'static str },
+    /// The cell covers a slot already covered by the cell at the node path
+    /// `other`; the later cell wins. Strict mode only.
+    OverlappingCells { other: String },
+    /// Only `covered` of the `width` slots of the row are covered by a cell.
+    /// Strict mode only.
+    RaggedRow { covered: usize, width: usize },
+    /// The cell spans `rowspan` rows, but its row group ends
+    /// `rows_in_group` rows from the cell's first row. The rowspan is clamped
+    /// to the group, or rows are added to the group with
+    /// [`ExtractOptions::clamp_rowspans`] off. Strict mode only.
+    RowspanCrossesRowGroup {
+        rowspan: usize,
+        rows_in_group: usize,
+    },
+}
+
+/// The tables and diagnostics of a lenient extraction.
+pub struct Extraction<'a> {
+    /// Every table that could be extracted, in document order.
+    pub tables: Vec<Table<Node<'a>>>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        extract_all_tables_with, extract_table_nodes_to_table_lenient,
+        extract_table_nodes_to_table_with, validate_tables, Error, Limit, Location,
+    };
+
+    const HTML: &str = r#"
+    <table><tr><td>small</td></tr></table>
+    <table>
+        <tr><td rowspan="50">tall</td><td colspan="3">wide</td></tr>
+        <tr><td>a</td><td>b</td></tr>
+        <tr><td>c</td></tr>
+    </table>
+    <table><tr><td>last</td><td><table><tr><td colspan="9">x</td></tr></table></td></tr></table>
+    "#;
+
+    /// Options keeping the tall rowspan of `HTML` from being clamped.
+    fn unclamped() -> ExtractOptions {
+        ExtractOptions::new().clamp_rowspans(false)
+    }
+
+    fn csvs(options: &ExtractOptions) -> Result<Vec<String>, Error> {
+        let package = sxd_html::parse_html(HTML);
+        let document = package.as_document();
+        extract_table_nodes_to_table_with(document.root(), options)?
+            .iter()
+            .map(|table| table.to_string_table().to_csv())
+            .collect()
+    }
+
+    fn exceeded(options: &ExtractOptions) -> (Limit, usize, usize, Location) {
+        match csvs(options) {
+            Err(Error::LimitExceeded {
+                limit,
+                max,
+                actual,
+                location,
+            }) => (limit, max, actual, *location),
+            other => panic!("expected a limit error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn reports_each_exceeded_limit() {
+        let (limit, max, actual, location) = exceeded(&unclamped().max_rows(10));
+        assert_eq!((limit, max, actual), (Limit::Rows, 10, 50));
+        assert_eq!(location.table, Some(1));
+        assert_eq!(
+            location.attribute,
+            Some(("rowspan".to_string(), "50".to_string()))
+        );
+        assert_eq!(location.path, "/html/body/table[2]/tbody/tr[1]/td[1]");
+
+        let (limit, max, actual, location) = exceeded(&unclamped().max_columns(3));
+        assert_eq!((limit, max, actual), (Limit::Columns, 3, 4));
+        assert_eq!((location.row, location.col), (Some(0), Some(1)));
+
+        let (limit, _, actual, _) = exceeded(&unclamped().max_cells(100));
+        assert_eq!((limit, actual), (Limit::Cells, 200));
+        let (limit, _, actual, _) = exceeded(&unclamped().max_span_area(5));
+        assert_eq!((limit, actual), (Limit::SpanArea, 50));
+
+        let (limit, max, actual, location) = exceeded(&unclamped().max_tables(2));
+        assert_eq!((limit, max, actual), (Limit::Tables, 2, 3));
+        assert_eq!(location.table, Some(2));
+    }
+
+    #[test]
+    fn truncates_offending_tables() {
+        let truncate = unclamped().on_limit(LimitAction::Truncate);
+        let tables = csvs(&truncate.clone().max_rows(2).max_columns(3)).unwrap();
+        assert_eq!(tables[1], "tall,wide,wide\ntall,a,b\n");
+        assert_eq!(tables[2], "last,x\n");
+
+        // The tall cell is clipped to 12 rows, leaving no room for the others.
+        let tables = csvs(&truncate.clone().max_cells(12)).unwrap();
+        assert_eq!(tables[1], "tall\n".repeat(12));
+
+        let tables = csvs(&truncate.clone().max_span_area(20)).unwrap();
+        assert_eq!(tables[1].lines().count(), 20);
+
+        let tables = csvs(&truncate.max_tables(1)).unwrap();
+        assert_eq!(tables, ["small\n"]);
+    }
+
+    #[test]
+    fn skips_offending_tables() {
+        let skip = unclamped().max_columns(8).on_limit(LimitAction::Skip);
+        assert_eq!(csvs(&skip).unwrap().len(), 3);
+
+        let package = sxd_html::parse_html(HTML);
+        let document = package.as_document();
+        let all = extract_all_tables_with(document.root(), &skip).unwrap();
+        assert_eq!(all.len(), 3);
+
+        let tables = csvs(&skip.max_rows(10)).unwrap();
+        assert_eq!(tables, ["small\n", "last,x\n"]);
+    }
+
+    #[test]
+    fn lenient_extraction_keeps_tables_and_reports_repairs() {
+        let package = sxd_html::parse_html(HTML);
+        let document = package.as_document();
+        let options = unclamped()
+            .max_rows(2)
+            .max_tables(2)
+            .on_limit(LimitAction::Truncate);
+        let extraction = extract_table_nodes_to_table_lenient(document.root(), &options).unwrap();
+        assert_eq!(extraction.tables.len(), 2);
+        assert_eq!(extraction.tables[1].size(), (2, 4));
+        let kinds: Vec<_> = extraction
+            .diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.kind.clone(), diagnostic.location.table))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                (
+                    DiagnosticKind::Truncated {
+                        limit: Limit::Tables,
+                        max: 2,
+                        actual: 3
+                    },
+                    Some(2)
+                ),
+                (
+                    DiagnosticKind::Truncated {
+                        limit: Limit::Rows,
+                        max: 2,
+                        actual: 50
+                    },
+                    Some(1)
+                ),
+                (
+                    DiagnosticKind::Truncated {
+                        limit: Limit::Rows,
+                        max: 2,
+                        actual: 3
+                    },
+                    Some(1)
+                ),
+            ]
+        );
+        assert_eq!(
+            extraction.diagnostics[2].to_string(),
+            "truncated to the rows limit: 3 > 2 (table 1, row 2, at /html/body/table[2]/tbody/tr[3])"
+        );
+
+        let limited = unclamped().max_rows(2);
+        let extraction = extract_table_nodes_to_table_lenient(document.root(), &limited).unwrap();
+        assert_eq!(extraction.tables.len(), 2);
+        assert!(matches!(
+            extraction.diagnostics[..],
+            [Diagnostic {
+                kind: DiagnosticKind::TableSkipped {
+                    limit: Limit::Rows,
+                    ..
+                },
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn many_invalid_spans_are_located_in_linear_time() {
+        // Locating each cell by scanning its sibling rows took minutes here.
+        let html = format!(
+            "<table>{}</table>",
+            r#"<tr><td colspan="x">a</td><td>b</td></tr>"#.repeat(10_000)
+        );
+        let package = sxd_html::parse_html(&html);
+        let root = package.as_document().root();
+        let tables = extract_table_nodes_to_table_with(root, &ExtractOptions::new()).unwrap();
+        assert_eq!(tables[0].size(), (10_000, 2));
+
+        let extraction =
+            extract_table_nodes_to_table_lenient(root, &ExtractOptions::new()).unwrap();
+        assert_eq!(extraction.diagnostics.len(), 10_000);
+        assert_eq!(
+            extraction.diagnostics[9_999].location.path,
+            "/html/body/table/tbody/tr[10000]/td[1]"
+        );
+    }
+
+    #[test]
+    fn strict_mode_reports_overlaps_and_fails_extraction() {
+        let package = sxd_html::parse_html(
+            r#"<table>
+                 <tr><td>a</td><td rowspan="2">b</td></tr>
+                 <tr><td colspan="2">c</td></tr>
+               </table>
+               <table><tr><td colspan="2">ok</td></tr><tr><td>1</td><td>2</td></tr></table>"#,
+        );
+        let root = package.as_document().root();
+        assert_eq!(
+            extract_table_nodes_to_table_with(root, &ExtractOptions::new())
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let findings = validate_tables(root, &ExtractOptions::new()).unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].kind,
+            DiagnosticKind::OverlappingCells {
+                other: "/html/body/table[1]/tbody/tr[1]/td[2]".to_string()
+            }
+        );
+        assert_eq!(
+            (findings[0].location.table, findings[0].location.row),
+            (Some(0), Some(1))
+        );
+
+        let strict = ExtractOptions::new().strict(true);
+        let Err(Error::Validation { findings }) = extract_table_nodes_to_table_with(root, &strict)
+        else {
+            panic!("expected strict extraction to fail");
+        };
+        assert_eq!(findings[0].location.table, Some(0));
+        assert_eq!(
+            Error::Validation { findings }.to_string(),
+            "table failed validation: cell overlaps the cell at \
+             /html/body/table[1]/tbody/tr[1]/td[2] (table 0, row 1, column 0, \
+             at /html/body/table[1]/tbody/tr[2]/td)"
+        );
+    }
+
+    #[test]
+    fn downward_growing_cells_respect_the_span_area() {
+        let html = format!(
+            r#"<table><tr><td rowspan="0" colspan="10">grow</td></tr>{}</table>"#,
+            "<tr><td>x</td></tr>".repeat(50)
+        );
+        let package = sxd_html::parse_html(&html);
+        let root = package.as_document().root();
+
+        let options = ExtractOptions::new().max_span_area(20);
+        let Err(Error::LimitExceeded {
+            limit,
+            max,
+            actual,
+            location,
+        }) = extract_table_nodes_to_table_with(root, &options)
+        else {
+            panic!("expected the growing cell to exceed the span area");
+        };
+        assert_eq!((limit, max, actual), (Limit::SpanArea, 20, 30));
+        assert_eq!(
+            location.attribute,
+            Some(("rowspan".to_string(), "0".to_string()))
+        );
+
+        let truncate = options.on_limit(LimitAction::Truncate);
+        let tables = extract_table_nodes_to_table_with(root, &truncate).unwrap();
+        let cell = tables[0].cell(0, 0).unwrap();
+        assert_eq!((cell.span.rowspan, cell.span.colspan), (2, 10));
+        assert_eq!(tables[0].size(), (51, 11));
+    }
+
+    #[test]
+    fn default_options_clamp_oversized_rowspans() {
+        let package = sxd_html::parse_html(
+            r#"<table><tr><td rowspan="65534" colspan="20">big</td></tr></table>"#,
+        );
+        let root = package.as_document().root();
+
+        let tables = extract_table_nodes_to_table_with(root, &ExtractOptions::new()).unwrap();
+        assert_eq!(tables[0].size(), (1, 20));
+
+        let Err(Error::LimitExceeded { limit, .. }) =
+            extract_table_nodes_to_table_with(root, &unclamped().max_span_area(100_000))
+        else {
+            panic!("expected the unclamped cell to exceed a limit");
+        };
+        assert_eq!(limit, Limit::SpanArea);
+    }
+}
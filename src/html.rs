@@ -0,0 +1,229 @@
+use std::{fmt::Display, io::Write, ops::Range};
+
+use crate::{
+    table::{Section, Table},
+    Error,
+};
+
+impl<T> Table<T>
+where
+    T: Display,
+{
+    /// Writes the table as an HTML `<table>` element.
+    ///
+    /// Cells with span metadata are written once with `rowspan`/`colspan`
+    /// when every slot they cover still holds the same text; otherwise each
+    /// slot is written as a cell of its own. Row groups become `<thead>`,
+    /// `<tbody>` and `<tfoot>`, and cells are written as `<th>` when they are
+    /// header cells or belong to a `<thead>` group. Rows outside any row group
+    /// are wrapped in `<tbody>`.
+    ///
+    /// Parsing the output with [`extract_table_nodes_to_table`] yields the
+    /// same grid of texts, with empty slots read back as empty cells.
+    ///
+    /// [`extract_table_nodes_to_table`]: crate::extract_table_nodes_to_table
+    pub fn write_html(&self, writer: &mut impl Write) -> Result<(), Error> {
+        let (rows, cols) = self.size();
+        let texts: Vec<Option<String>> = (0..rows)
+            .flat_map(|row| (0..cols).map(move |col| (row, col)))
+            .map(|(row, col)| self.cell(row, col).map(|cell| cell.value.to_string()))
+            .collect();
+        let mut covered = vec![false; rows * cols];
+        writer.write_all(b"<table>\n")?;
+        for (section, group_rows) in self.html_sections() {
+            let tag = match section {
+                Some(Section::Head) => "thead",
+                Some(Section::Foot) => "tfoot",
+                Some(Section::Body) | None => "tbody",
+            };
+            writeln!(writer, "<{tag}>")?;
+            for row in group_rows.clone() {
+                writer.write_all(b"<tr>")?;
+                for col in 0..cols {
+                    if covered[row * cols + col] {
+                        continue;
+                    }
+                    let cell = self.cell(row, col);
+                    let (rowspan, colspan) = match cell {
+                        Some(cell) if self.can_merge(&texts, &covered, &group_rows, row, col) => {
+                            (cell.span.rowspan, cell.span.colspan)
+                        }
+                        _ => (1, 1),
+                    };
+                    for i in row..row + rowspan {
+                        for j in col..col + colspan {
+                            covered[i * cols + j] = true;
+                        }
+                    }
+                    let name = match cell {
+                        Some(cell) if cell.header => "th",
+                        _ if section == Some(Section::Head) => "th",
+                        _ => "td",
+                    };
+                    write!(writer, "<{name}")?;
+                    if rowspan > 1 {
+                        write!(writer, " rowspan=\"{rowspan}\"")?;
+                    }
+                    if colspan > 1 {
+                        write!(writer, " colspan=\"{colspan}\"")?;
+                    }
+                    writer.write_all(b">")?;
+                    if let Some(text) = &texts[row * cols + col] {
+                        write_escaped(writer, text)?;
+                    }
+                    write!(writer, "</{name}>")?;
+                }
+                writer.write_all(b"</tr>\n")?;
+            }
+            writeln!(writer, "</{tag}>")?;
+        }
+        writer.write_all(b"</table>\n")?;
+        Ok(())
+    }
+
+    pub fn to_html(&self) -> Result<String, Error> {
+        let mut buf = Vec::new();
+        self.write_html(&mut buf)?;
+        crate::utf8_output(buf)
+    }
+
+    /// Returns `true` if the cell anchored at `(row, col)` can be written with
+    /// its recorded span: it stays inside the row group and every slot it
+    /// covers is free and holds the same cell and text.
+    fn can_merge(
+        &self,
+        texts: &[Option<String>],
+        covered: &[bool],
+        group_rows: &Range<usize>,
+        row: usize,
+        col: usize,
+    ) -> bool {
+        let (_, cols) = self.size();
+        let Some(origin) = self.cell(row, col) else {
+            return false;
+        };
+        let span = origin.span;
+        if !origin.is_origin() || (span.rowspan == 1 && span.colspan == 1) {
+            return false;
+        }
+        if row + span.rowspan > group_rows.end || col + span.colspan > cols {
+            return false;
+        }
+        (row..row + span.rowspan).all(|i| {
+            (col..col + span.colspan).all(|j| {
+                !covered[i * cols + j]
+                    && self.cell(i, j).is_some_and(|cell| cell.span == span)
+                    && texts[i * cols + j] == texts[row * cols + col]
+            })
+        })
+    }
+
+    /// Splits the rows into the sections written as `<thead>`, `<tbody>` and
+    /// `<tfoot>`, skipping empty row groups.
+    fn html_sections(&self) -> Vec<(Option<Section>, Range<usize>)> {
+        let (rows, _) = self.size();
+        let mut sections = vec![];
+        let mut start = 0;
+        for row in 0..rows {
+            let group = self.row_group(row);
+            if row + 1 == rows || group != self.row_group(row + 1) {
+                sections.push((group.map(|group| group.section), start..row + 1));
+                start = row + 1;
+            }
+        }
+        sections
+    }
+}
+
+fn write_escaped(writer: &mut impl Write, text: &str) -> std::io::Result<()> {
+    let mut last = 0;
+    for (index, ch) in text.char_indices() {
+        let escaped = match ch {
+            '&' => "&amp;",
+            '<' => "&lt;",
+            '>' => "&gt;",
+            '"' => "&quot;",
+            _ => continue,
+        };
+        writer.write_all(&text.as_bytes()[last..index])?;
+        writer.write_all(escaped.as_bytes())?;
+        last = index + ch.len_utf8();
+    }
+    writer.write_all(&text.as_bytes()[last..])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{extract_table_nodes_to_table, Table};
+
+    fn parse_string_tables(html: &str) -> Vec<Table<String>> {
+        let package = sxd_html::parse_html(html);
+        let document = package.as_document();
+        extract_table_nodes_to_table(document.root())
+            .unwrap()
+            .iter()
+            .map(Table::to_string_table)
+            .collect()
+    }
+
+    #[test]
+    fn writes_spans_sections_and_escapes() {
+        let html = r#"
+        <table>
+            <thead><tr><th colspan="2">A &amp; B</th></tr></thead>
+            <tbody>
+                <tr><td rowspan="2">x</td><td>&lt;1&gt;</td></tr>
+                <tr><td>2</td></tr>
+            </tbody>
+        </table>
+        "#;
+        let table = &parse_string_tables(html)[0];
+
+        assert_eq!(
+            table.to_html().unwrap(),
+            "<table>\n\
+             <thead>\n\
+             <tr><th colspan=\"2\">A &amp; B</th></tr>\n\
+             </thead>\n\
+             <tbody>\n\
+             <tr><td rowspan=\"2\">x</td><td>&lt;1&gt;</td></tr>\n\
+             <tr><td>2</td></tr>\n\
+             </tbody>\n\
+             </table>\n"
+        );
+    }
+
+    #[test]
+    fn output_reparses_into_the_same_grid() {
+        let html = r#"
+        <table>
+            <tr><td>a</td><td>b</td><td rowspan="2">c</td><td>d</td></tr>
+            <tr><td>e</td><td colspan="3">f</td></tr>
+            <tr><td>i</td><td rowspan="0">j</td><td>k</td></tr>
+            <tr><td>m</td></tr>
+            <tfoot><tr><th>total</th><td></td></tr></tfoot>
+        </table>
+        "#;
+        let table = &parse_string_tables(html)[0];
+        let reparsed = &parse_string_tables(&table.to_html().unwrap())[0];
+
+        assert_eq!(reparsed.to_csv().unwrap(), table.to_csv().unwrap());
+        assert_eq!(reparsed.row_groups(), table.row_groups());
+    }
+
+    #[test]
+    fn unmerged_slots_are_written_separately() {
+        let mut table = Table::new((2, 2));
+        table.set_spanning(0, 0, 1, 2, "a".to_string());
+        table.set(1, 0, "b".to_string());
+        table.set(0, 1, "changed".to_string());
+
+        assert_eq!(
+            table.to_html().unwrap(),
+            "<table>\n<tbody>\n\
+             <tr><td>a</td><td>changed</td></tr>\n\
+             <tr><td>b</td><td></td></tr>\n\
+             </tbody>\n</table>\n"
+        );
+    }
+}
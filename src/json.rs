@@ -0,0 +1,283 @@
+use std::{collections::HashSet, fmt::Display, io::Write};
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    records::{column_names, HeaderRows},
+    table::{Cell, Table},
+    Error,
+};
+
+/// Shape of the JSON document written by [`Table::write_json`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JsonLayout {
+    /// An array of rows, each an array of cells: `[["a", "b"], ["c", null]]`.
+    #[default]
+    Arrays,
+    /// An array of objects keyed by the header names:
+    /// `[{"name": "c", "age": null}]`.
+    Objects,
+}
+
+/// Options for the JSON writers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonOptions {
+    layout: JsonLayout,
+    header_rows: HeaderRows,
+    spans: bool,
+}
+
+impl Default for JsonOptions {
+    fn default() -> Self {
+        Self {
+            layout: JsonLayout::Arrays,
+            header_rows: HeaderRows::Detect,
+            spans: false,
+        }
+    }
+}
+
+impl JsonOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn layout(mut self, layout: JsonLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Sets which leading rows provide the object keys for
+    /// [`JsonLayout::Objects`] and NDJSON. Defaults to
+    /// [`HeaderRows::Detect`], as for [`Table::to_records`].
+    ///
+    /// Multi-row headers are joined like [`Records::columns`].
+    ///
+    /// [`Records::columns`]: crate::Records::columns
+    pub fn header_rows(mut self, header_rows: HeaderRows) -> Self {
+        self.header_rows = header_rows;
+        self
+    }
+
+    /// Writes every cell as an object carrying its span metadata instead of a
+    /// bare string:
+    /// `{"value": "a", "row": 0, "col": 0, "rowspan": 2, "colspan": 1, "origin": true}`.
+    pub fn spans(mut self, spans: bool) -> Self {
+        self.spans = spans;
+        self
+    }
+}
+
+impl<T> Table<T>
+where
+    T: Display,
+{
+    /// Builds the JSON document described by `options`.
+    ///
+    /// Empty grid slots become `null`. Objects keep the column order. Columns
+    /// without a name are keyed `column_1`, `column_2`, … by position, and
+    /// a name shared by several columns gets a `_2`, `_3`, … suffix after
+    /// its first use, so no value is lost.
+    pub fn to_json_value(&self, options: &JsonOptions) -> Value {
+        match options.layout {
+            JsonLayout::Arrays => Value::Array(
+                (0..self.size().0)
+                    .map(|row| Value::Array(self.json_row(row, options)))
+                    .collect(),
+            ),
+            JsonLayout::Objects => Value::Array(self.json_objects(options).collect()),
+        }
+    }
+
+    /// Writes the JSON document described by `options`.
+    ///
+    /// Rows are serialized one at a time straight into `writer`, producing
+    /// the same text as serializing [`Table::to_json_value`].
+    pub fn write_json(&self, writer: &mut impl Write, options: &JsonOptions) -> Result<(), Error> {
+        let rows: Box<dyn Iterator<Item = Value>> = match options.layout {
+            JsonLayout::Arrays => {
+                Box::new((0..self.size().0).map(|row| Value::Array(self.json_row(row, options))))
+            }
+            JsonLayout::Objects => Box::new(self.json_objects(options)),
+        };
+        writer.write_all(b"[")?;
+        for (index, row) in rows.enumerate() {
+            if index > 0 {
+                writer.write_all(b",")?;
+            }
+            serde_json::to_writer(&mut *writer, &row).map_err(|err| Error::Io(err.into()))?;
+        }
+        writer.write_all(b"]")?;
+        Ok(())
+    }
+
+    pub fn to_json(&self, options: &JsonOptions) -> Result<String, Error> {
+        let mut buf = Vec::new();
+        self.write_json(&mut buf, options)?;
+        crate::utf8_output(buf)
+    }
+
+    /// Writes one JSON object per body row, each followed by a newline.
+    ///
+    /// Objects are keyed as with [`JsonLayout::Objects`]; the layout option
+    /// is ignored. Rows are written as they are built, so the output can be
+    /// consumed as a stream.
+    pub fn write_ndjson(
+        &self,
+        writer: &mut impl Write,
+        options: &JsonOptions,
+    ) -> Result<(), Error> {
+        for object in self.json_objects(options) {
+            serde_json::to_writer(&mut *writer, &object).map_err(|err| Error::Io(err.into()))?;
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    pub fn to_ndjson(&self, options: &JsonOptions) -> Result<String, Error> {
+        let mut buf = Vec::new();
+        self.write_ndjson(&mut buf, options)?;
+        crate::utf8_output(buf)
+    }
+
+    fn json_objects<'t>(&'t self, options: &'t JsonOptions) -> impl Iterator<Item = Value> + 't {
+        let header_rows = self.header_row_count(options.header_rows);
+        let keys = unique_keys(column_names(self, header_rows, ToString::to_string));
+        (header_rows..self.size().0).map(move |row| {
+            let object: Map<String, Value> = keys
+                .iter()
+                .cloned()
+                .zip(self.json_row(row, options))
+                .collect();
+            Value::Object(object)
+        })
+    }
+
+    fn json_row(&self, row: usize, options: &JsonOptions) -> Vec<Value> {
+        (0..self.size().1)
+            .map(|col| match self.cell(row, col) {
+                None => Value::Null,
+                Some(cell) if options.spans => json_cell_with_span(&cell),
+                Some(cell) => Value::String(cell.value.to_string()),
+            })
+            .collect()
+    }
+}
+
+/// Turns column names into distinct object keys, naming blank columns by
+/// position and suffixing repeated names with `_2`, `_3`, ….
+fn unique_keys(names: Vec<String>) -> Vec<String> {
+    let mut used = HashSet::new();
+    names
+        .into_iter()
+        .enumerate()
+        .map(|(col, name)| {
+            let name = if name.is_empty() {
+                format!("column_{}", col + 1)
+            } else {
+                name
+            };
+            let mut key = name.clone();
+            let mut suffix = 1;
+            while !used.insert(key.clone()) {
+                suffix += 1;
+                key = format!("{name}_{suffix}");
+            }
+            key
+        })
+        .collect()
+}
+
+fn json_cell_with_span<T: Display>(cell: &Cell<'_, T>) -> Value {
+    json!({
+        "value": cell.value.to_string(),
+        "row": cell.span.row,
+        "col": cell.span.col,
+        "rowspan": cell.span.rowspan,
+        "colspan": cell.span.colspan,
+        "origin": cell.is_origin(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Table<String> {
+        let mut table = Table::new((3, 2));
+        table.set(0, 0, "name".to_string());
+        table.set(0, 1, "age".to_string());
+        table.set(1, 0, "Alice".to_string());
+        table.set(1, 1, "30".to_string());
+        table.set(2, 0, "Bob".to_string());
+        table.set_header(0, 0, true);
+        table.set_header(0, 1, true);
+        table
+    }
+
+    #[test]
+    fn writes_arrays_and_objects_with_nulls() {
+        let table = sample();
+
+        assert_eq!(
+            table.to_json(&JsonOptions::new()).unwrap(),
+            r#"[["name","age"],["Alice","30"],["Bob",null]]"#
+        );
+        assert_eq!(
+            table
+                .to_json(&JsonOptions::new().layout(JsonLayout::Objects))
+                .unwrap(),
+            r#"[{"name":"Alice","age":"30"},{"name":"Bob","age":null}]"#
+        );
+        assert_eq!(
+            table.to_ndjson(&JsonOptions::new()).unwrap(),
+            "{\"name\":\"Alice\",\"age\":\"30\"}\n{\"name\":\"Bob\",\"age\":null}\n"
+        );
+    }
+
+    #[test]
+    fn includes_span_metadata_on_request() {
+        let mut table = Table::new((1, 2));
+        table.set_spanning(0, 0, 1, 2, "a".to_string());
+
+        assert_eq!(
+            table.to_json_value(&JsonOptions::new().spans(true)),
+            json!([[
+                {"value": "a", "row": 0, "col": 0, "rowspan": 1, "colspan": 2, "origin": true},
+                {"value": "a", "row": 0, "col": 0, "rowspan": 1, "colspan": 2, "origin": false},
+            ]])
+        );
+    }
+
+    #[test]
+    fn detects_multi_row_headers_and_keeps_duplicate_columns() {
+        let html = r#"
+        <table>
+            <thead>
+                <tr><th colspan="2">X</th><th>Z</th></tr>
+                <tr><th>Y</th><th>Y</th><th></th></tr>
+            </thead>
+            <tr><td>a</td><td>b</td><td>c</td></tr>
+        </table>
+        "#;
+        let package = sxd_html::parse_html(html);
+        let document = package.as_document();
+        let tables = crate::extract_table_nodes_to_table(document.root()).unwrap();
+        let table = tables[0].to_string_table();
+
+        assert_eq!(
+            table.to_ndjson(&JsonOptions::new()).unwrap(),
+            "{\"X / Y\":\"a\",\"X / Y_2\":\"b\",\"Z\":\"c\"}\n"
+        );
+        let headerless = JsonOptions::new().header_rows(HeaderRows::Count(0));
+        assert_eq!(
+            table
+                .to_ndjson(&headerless)
+                .unwrap()
+                .lines()
+                .next()
+                .unwrap(),
+            r#"{"column_1":"X","column_2":"X","column_3":"Z"}"#
+        );
+    }
+}
@@ -1,23 +1,173 @@
 pub mod element_utils;
+pub mod extract;
+pub mod html;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod links;
+pub mod markdown;
 pub mod node_utils;
+pub mod records;
+pub mod select;
 pub mod table;
-pub use crate::node_utils::extract_table_nodes_to_table;
+pub mod text;
+pub mod tree;
+pub mod xpath;
+pub use crate::extract::ExtractOptions;
+pub use crate::node_utils::{
+    extract_table_nodes_to_table, extract_table_nodes_to_table_lenient,
+    extract_table_nodes_to_table_with, validate_tables,
+};
+pub use crate::records::{HeaderRows, Record, Records};
+pub use crate::select::{
+    select_table, select_table_with, select_tables, select_tables_with, TableSelector,
+};
 pub use crate::table::Table;
+pub use crate::text::TextOptions;
+pub use crate::tree::{
+    extract_all_tables, extract_all_tables_with, extract_table_tree, extract_table_tree_with,
+};
+
+/// An extraction limit that a table exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Limit {
+    /// Number of grid columns of a table.
+    Columns,
+    /// Number of grid rows of a table.
+    Rows,
+    /// Number of grid slots of a table, `rows * columns`.
+    Cells,
+    /// `rowspan * colspan` of a single cell.
+    SpanArea,
+    /// Number of top-level tables in a document.
+    Tables,
+}
+
+impl std::fmt::Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Columns => "columns",
+            Self::Rows => "rows",
+            Self::Cells => "cells",
+            Self::SpanArea => "span area",
+            Self::Tables => "tables",
+        })
+    }
+}
+
+/// Where in a document an extraction error was found.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Location {
+    /// Index of the table among the top-level tables of the document.
+    pub table: Option<usize>,
+    /// Grid row of the offending cell.
+    pub row: Option<usize>,
+    /// Grid column of the offending cell.
+    pub col: Option<usize>,
+    /// Path of the offending element from the document root, as returned by
+    /// [`node_utils::node_path`].
+    pub path: String,
+    /// The attribute that led to the error, as `(name, value)` written in
+    /// the document.
+    pub attribute: Option<(String, String)>,
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = vec![];
+        if let Some(table) = self.table {
+            parts.push(format!("table {table}"));
+        }
+        if let Some(row) = self.row {
+            parts.push(format!("row {row}"));
+        }
+        if let Some(col) = self.col {
+            parts.push(format!("column {col}"));
+        }
+        if let Some((name, value)) = &self.attribute {
+            parts.push(format!("{name}={value:?}"));
+        }
+        if !self.path.is_empty() {
+            parts.push(format!("at {}", self.path));
+        }
+        f.write_str(&parts.join(", "))
+    }
+}
 
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum Error {
-    TableNotFound,
-    InvalidDocument(&'static str),
+    /// No table was found: either the document has no tables at all, or none
+    /// of them satisfied a [`select`] criterion.
+    TableNotFound {
+        /// The failed [`select`] criterion, or `None` when the document has
+        /// no tables.
+        criterion: Option<String>,
+    },
+    /// A table grew past one of the [`ExtractOptions`] limits.
+    LimitExceeded {
+        limit: Limit,
+        /// The largest allowed value.
+        max: usize,
+        /// The value the table would have reached.
+        actual: usize,
+        location: Box<Location>,
+    },
+    /// A table did not conform to the HTML table model in
+    /// [strict](ExtractOptions::strict) mode.
+    Validation { findings: Vec<extract::Diagnostic> },
+    /// The CSV writer rejected a record.
     FailedToConvertToCSV,
+    /// An XPath expression could not be compiled or evaluated.
     XPathEvaluationError(Box<dyn std::error::Error + Send + Sync + 'static>),
+    /// A record could not be deserialized. Requires the `serde` feature.
+    #[cfg(feature = "serde")]
+    Deserialize {
+        row: usize,
+        column: Option<usize>,
+        message: String,
+    },
+    /// Writing a table failed, either in the underlying writer or, for the
+    /// `to_*` methods returning a `String`, because the output was not
+    /// valid UTF-8.
+    Io(std::io::Error),
+    /// A CSV cell would start a spreadsheet formula and the
+    /// [`InjectionPolicy`](table::InjectionPolicy) rejects such cells.
+    CsvInjection { row: usize, col: usize },
+    /// A [`TableSelector::Css`] selector is malformed or uses unsupported
+    /// syntax.
+    InvalidSelector(String),
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::TableNotFound => f.write_str("no table found in document"),
-            Self::InvalidDocument(ctx) => write!(f, "invalid document: {ctx}"),
+            Self::TableNotFound { criterion: None } => f.write_str("no table found in document"),
+            Self::TableNotFound {
+                criterion: Some(criterion),
+            } => write!(f, "no table matched {criterion}"),
+            Self::LimitExceeded {
+                limit,
+                max,
+                actual,
+                location,
+            } => {
+                write!(f, "{limit} limit exceeded: {actual} > {max}")?;
+                if **location != Location::default() {
+                    write!(f, " ({location})")?;
+                }
+                Ok(())
+            }
+            Self::Validation { findings } => {
+                write!(f, "table failed validation")?;
+                if let Some(first) = findings.first() {
+                    write!(f, ": {first}")?;
+                }
+                if findings.len() > 1 {
+                    write!(f, " and {} more", findings.len() - 1)?;
+                }
+                Ok(())
+            }
             Self::FailedToConvertToCSV => f.write_str("failed to convert table to CSV"),
             Self::XPathEvaluationError(err)
                 if matches!(
@@ -28,6 +178,27 @@ impl std::fmt::Display for Error {
                 f.write_str("failed to evaluate XPath: XPath was empty")
             }
             Self::XPathEvaluationError(err) => write!(f, "failed to evaluate XPath: {err}"),
+            #[cfg(feature = "serde")]
+            Self::Deserialize {
+                row,
+                column: Some(column),
+                message,
+            } => write!(
+                f,
+                "failed to deserialize row {row}, column {column}: {message}"
+            ),
+            #[cfg(feature = "serde")]
+            Self::Deserialize {
+                row,
+                column: None,
+                message,
+            } => write!(f, "failed to deserialize row {row}: {message}"),
+            Self::Io(err) => write!(f, "I/O error: {err}"),
+            Self::CsvInjection { row, col } => write!(
+                f,
+                "cell at row {row}, column {col} could be interpreted as a spreadsheet formula"
+            ),
+            Self::InvalidSelector(ctx) => write!(f, "invalid selector: {ctx}"),
         }
     }
 }
@@ -36,8 +207,47 @@ impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             Self::XPathEvaluationError(err) => Some(err.as_ref()),
-            Self::TableNotFound | Self::InvalidDocument(_) | Self::FailedToConvertToCSV => None,
+            Self::Io(err) => Some(err),
+            Self::TableNotFound { .. }
+            | Self::LimitExceeded { .. }
+            | Self::Validation { .. }
+            | Self::FailedToConvertToCSV
+            | Self::CsvInjection { .. }
+            | Self::InvalidSelector(_) => None,
+            #[cfg(feature = "serde")]
+            Self::Deserialize { .. } => None,
+        }
+    }
+}
+
+impl Error {
+    /// Records the index of the table the error occurred in.
+    pub(crate) fn in_table(mut self, index: usize) -> Self {
+        match &mut self {
+            Self::LimitExceeded { location, .. } => {
+                location.table.get_or_insert(index);
+            }
+            Self::Validation { findings } => {
+                for finding in findings {
+                    finding.location.table.get_or_insert(index);
+                }
+            }
+            _ => {}
         }
+        self
+    }
+}
+
+/// Converts the output written into a buffer to a `String`, failing with
+/// [`Error::Io`] if it is not valid UTF-8.
+pub(crate) fn utf8_output(buf: Vec<u8>) -> Result<String, Error> {
+    String::from_utf8(buf)
+        .map_err(|err| Error::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, err)))
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
     }
 }
 
@@ -50,6 +260,7 @@ impl From<sxd_xpath::Error> for Error {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::table::{CellSpan, ColumnDef, ColumnGroup, RowGroup, Section, SpanFill, TableInfo};
     use std::error::Error as StdError;
 
     fn extract_table_texts_from_document(html: &str) -> Result<Vec<Table<String>>, Error> {
@@ -133,12 +344,18 @@ mod tests {
         </html>
         "#;
         let result = extract_table_texts_from_document(html);
-        assert!(matches!(result, Err(Error::TableNotFound)));
+        assert!(matches!(
+            result,
+            Err(Error::TableNotFound { criterion: None })
+        ));
 
         // empty html
         let html = r#""#;
         let result = extract_table_texts_from_document(html);
-        assert!(matches!(result, Err(Error::TableNotFound)));
+        assert!(matches!(
+            result,
+            Err(Error::TableNotFound { criterion: None })
+        ));
     }
 
     #[test]
@@ -400,58 +617,257 @@ mod tests {
         assert_eq!(result[6].to_csv().unwrap(), "a,b\na,c\na,d\n");
     }
 
+    #[test]
+    fn test_table_info() {
+        let html = r#"
+        <html>
+            <body>
+                <table id="prices" class="wikitable  sortable" summary="Fruit" aria-label="Prices">
+                    <caption> Fruit
+                        prices </caption>
+                    <colgroup span="2"></colgroup>
+                    <colgroup class="numbers"><col span="2" class="qty"><col id="total"></colgroup>
+                    <tr><td>a</td></tr>
+                </table>
+                <table></table>
+            </body>
+        </html>
+        "#;
+        let package = sxd_html::parse_html(html);
+        let document = package.as_document();
+        let tables = extract_table_nodes_to_table(document.root()).unwrap();
+        let strings = tables[0].to_string_table();
+        let info = strings.info();
+        assert_eq!(info.caption.as_deref(), Some("Fruit prices"));
+        assert_eq!(info.id.as_deref(), Some("prices"));
+        assert_eq!(info.classes, ["wikitable", "sortable"]);
+        assert_eq!(info.summary.as_deref(), Some("Fruit"));
+        assert_eq!(info.aria_label.as_deref(), Some("Prices"));
+        assert_eq!(
+            info.column_groups,
+            [
+                ColumnGroup {
+                    span: 2,
+                    ..ColumnGroup::default()
+                },
+                ColumnGroup {
+                    span: 3,
+                    classes: vec!["numbers".to_string()],
+                    cols: vec![
+                        ColumnDef {
+                            span: 2,
+                            classes: vec!["qty".to_string()],
+                            ..ColumnDef::default()
+                        },
+                        ColumnDef {
+                            span: 1,
+                            id: Some("total".to_string()),
+                            ..ColumnDef::default()
+                        },
+                    ],
+                    ..ColumnGroup::default()
+                },
+            ]
+        );
+
+        let source = tables[0].source_node().unwrap();
+        assert_eq!(
+            source.element().unwrap().attribute_value("id"),
+            Some("prices")
+        );
+        assert!(tables[1].source_node().is_none());
+        assert_eq!(tables[1].info(), &TableInfo::default());
+
+        let filled = tables[0].fill_spans(SpanFill::Empty);
+        assert_eq!(filled.info(), info);
+        assert_eq!(filled.source_node(), Some(source));
+        let mapped = tables[0].map(|_, _, node| *node);
+        assert_eq!(mapped.info(), info);
+        assert_eq!(mapped.source_node(), Some(source));
+    }
+
+    #[test]
+    fn test_column_attributes_and_hidden_columns() {
+        let html = r#"
+        <table>
+            <colgroup class="labels" width="40%"></colgroup>
+            <colgroup style="visibility: collapse"><col class="helper"></colgroup>
+            <colgroup>
+                <col span="2" class="amount" style="text-align: right" width="20">
+                <col hidden>
+            </colgroup>
+            <tr><td>Revenue</td><td>x</td><td colspan="2">10</td><td>y</td><td>z</td></tr>
+            <tr><td colspan="3">Costs</td><td>4</td><td>y</td><td>z</td></tr>
+        </table>
+        "#;
+        let package = sxd_html::parse_html(html);
+        let document = package.as_document();
+        let tables = extract_table_nodes_to_table(document.root()).unwrap();
+        let table = tables[0].to_string_table();
+
+        assert_eq!(table.columns().len(), 5);
+        assert_eq!(table.column(0).unwrap().classes, ["labels"]);
+        assert_eq!(table.column(0).unwrap().width.as_deref(), Some("40%"));
+        assert!(table.column(1).unwrap().hidden);
+        assert_eq!(table.column(1).unwrap().classes, ["helper"]);
+        assert_eq!(table.column(3).unwrap().width.as_deref(), Some("20"));
+        assert_eq!(
+            table.column(3).unwrap().style.as_deref(),
+            Some("text-align: right")
+        );
+        assert!(table.column(4).unwrap().hidden);
+        assert!(table.column(5).is_none());
+
+        let visible = table.drop_hidden_columns();
+        assert_eq!(
+            visible.to_csv().unwrap(),
+            "Revenue,10,10,z
+Costs,Costs,4,z
+"
+        );
+        assert_eq!(visible.columns().len(), 3);
+        assert_eq!(visible.column(1).unwrap().classes, ["amount"]);
+        let costs = visible.cell(1, 1).unwrap();
+        assert_eq!((costs.span.col, costs.span.colspan), (0, 2));
+        assert_eq!(visible.cell(0, 1).unwrap().span.colspan, 2);
+
+        let filled = table.fill_spans(SpanFill::Empty);
+        assert_eq!(filled.columns(), table.columns());
+        assert_eq!(
+            filled.drop_hidden_columns().to_csv().unwrap(),
+            "Revenue,10,,z
+Costs,,4,z
+"
+        );
+    }
+
+    #[test]
+    fn test_direct_col_elements_set_width() {
+        // HTML parsers wrap `<col>` in a `<colgroup>`, but XHTML keeps it as
+        // a child of the table.
+        let xml = r#"<table><col span="3"/><col/><tr><td>a</td></tr></table>"#;
+        let package = sxd_document::parser::parse(xml).unwrap();
+        let document = package.as_document();
+        let tables = extract_table_nodes_to_table(document.root()).unwrap();
+        let table = tables[0].to_string_table();
+        assert_eq!(table.size(), (1, 4));
+        assert_eq!(table.columns().len(), 4);
+        assert_eq!(table.to_csv().unwrap(), "a,,,\n");
+    }
+
+    #[test]
+    fn test_span_metadata() {
+        let html = r#"
+        <html>
+            <body>
+                <table>
+                    <tr><td rowspan="2" colspan="2">A</td><td>B</td></tr>
+                    <tr><td>C</td></tr>
+                </table>
+            </body>
+        </html>
+        "#;
+        let package = sxd_html::parse_html(html);
+        let document = package.as_document();
+        let tables = extract_table_nodes_to_table(document.root()).unwrap();
+        let table = tables[0].to_string_table();
+        let merged = table.cell(1, 1).unwrap();
+        assert!(merged.is_continuation());
+        assert_eq!(
+            merged.span,
+            CellSpan {
+                row: 0,
+                col: 0,
+                rowspan: 2,
+                colspan: 2,
+            }
+        );
+        assert!(table.cell(1, 2).unwrap().is_origin());
+        assert_eq!(
+            table.fill_spans(SpanFill::Empty).to_csv().unwrap(),
+            "A,,B\n,,C\n"
+        );
+    }
+
     #[test]
     fn test_rejects_when_rowspan_fills_column_limit() {
-        let html = format!(
-            r#"
+        let html = r#"
         <html>
             <body>
                 <table>
-                    <tr><td rowspan="0" colspan="{}">A</td></tr>
+                    <tr><td rowspan="0" colspan="1000">A</td></tr>
                     <tr><td>B</td></tr>
                 </table>
             </body>
         </html>
-        "#,
-            crate::node_utils::MAX_TABLE_COLUMNS
+        "#;
+        let Err(Error::LimitExceeded {
+            limit,
+            max,
+            actual,
+            location,
+        }) = extract_table_texts_from_document(html)
+        else {
+            panic!("expected table extraction to fail");
+        };
+        assert_eq!((limit, max, actual), (Limit::Columns, 1000, 1001));
+        assert_eq!(
+            *location,
+            Location {
+                table: Some(0),
+                row: Some(1),
+                col: Some(1000),
+                path: "/html/body/table/tbody/tr[2]/td".to_string(),
+                attribute: None,
+            }
         );
-        match extract_table_texts_from_document(&html) {
-            Err(Error::InvalidDocument(_)) => {}
-            Err(_) => panic!("expected InvalidDocument"),
-            Ok(_) => panic!("expected table extraction to fail"),
-        }
     }
 
     #[test]
-    fn test_rejects_colspan_larger_than_column_limit() {
-        let html = format!(
-            r#"
+    fn test_colspan_is_capped_at_1000() {
+        let html = r#"
         <html>
             <body>
                 <table>
-                    <tr><td colspan="{}">A</td></tr>
+                    <tr><td colspan="1001">A</td></tr>
                 </table>
             </body>
         </html>
-        "#,
-            crate::node_utils::MAX_TABLE_COLUMNS + 1
-        );
-        match extract_table_texts_from_document(&html) {
-            Err(Error::InvalidDocument(_)) => {}
-            Err(_) => panic!("expected InvalidDocument"),
-            Ok(_) => panic!("expected table extraction to fail"),
-        }
+        "#;
+        let result = extract_table_texts_from_document(html).unwrap();
+        assert_eq!(result[0].size(), (1, 1000));
+        assert_eq!(result[0].cell(0, 999).unwrap().span.colspan, 1000);
     }
 
     #[test]
     fn test_error_display() {
         assert_eq!(
-            Error::TableNotFound.to_string(),
+            Error::TableNotFound { criterion: None }.to_string(),
             "no table found in document"
         );
         assert_eq!(
-            Error::InvalidDocument("XPath ./tbody/tr did not return a nodeset").to_string(),
-            "invalid document: XPath ./tbody/tr did not return a nodeset"
+            Error::TableNotFound {
+                criterion: Some("table index 3".to_string())
+            }
+            .to_string(),
+            "no table matched table index 3"
+        );
+        assert_eq!(
+            Error::LimitExceeded {
+                limit: Limit::Columns,
+                max: 1000,
+                actual: 1004,
+                location: Box::new(Location {
+                    table: Some(2),
+                    row: Some(3),
+                    col: Some(998),
+                    path: "/html/body/table[3]/tbody/tr[4]/td[2]".to_string(),
+                    attribute: Some(("colspan".to_string(), "6".to_string())),
+                }),
+            }
+            .to_string(),
+            "columns limit exceeded: 1004 > 1000 (table 2, row 3, column 998, \
+             colspan=\"6\", at /html/body/table[3]/tbody/tr[4]/td[2])"
         );
         assert_eq!(
             Error::FailedToConvertToCSV.to_string(),
@@ -461,6 +877,16 @@ mod tests {
             Error::from(sxd_xpath::Error::NoXPath).to_string(),
             "failed to evaluate XPath: XPath was empty"
         );
+        #[cfg(feature = "serde")]
+        assert_eq!(
+            Error::Deserialize {
+                row: 2,
+                column: Some(1),
+                message: "invalid digit found in string".to_string(),
+            }
+            .to_string(),
+            "failed to deserialize row 2, column 1: invalid digit found in string"
+        );
     }
 
     #[test]
@@ -480,13 +906,13 @@ mod tests {
     #[test]
     fn error_converts_to_boxed_error_with_question_mark() {
         fn use_question_mark() -> Result<(), Box<dyn StdError>> {
-            Err(Error::InvalidDocument("context"))?;
+            Err(Error::TableNotFound { criterion: None })?;
             Ok(())
         }
 
         assert_eq!(
             use_question_mark().unwrap_err().to_string(),
-            "invalid document: context"
+            "no table found in document"
         );
     }
 
@@ -548,6 +974,96 @@ mod tests {
         assert_eq!(result[0].to_csv().unwrap(), "Alice,30\nTotal,1\n");
     }
 
+    #[test]
+    fn test_xhtml_tables_without_tbody() {
+        let xml = r#"<?xml version="1.0"?>
+        <html:html xmlns:html="http://www.w3.org/1999/xhtml">
+            <html:body>
+                <html:table>
+                    <html:tr><html:th>a</html:th><html:th>b</html:th></html:tr>
+                    <html:tr><html:td colspan="2">c</html:td></html:tr>
+                    <html:tfoot><html:tr><html:td>d</html:td><html:td>e</html:td></html:tr></html:tfoot>
+                </html:table>
+            </html:body>
+        </html:html>
+        "#;
+        let package = sxd_document::parser::parse(xml).unwrap();
+        let document = package.as_document();
+        let tables = extract_table_nodes_to_table(document.root()).unwrap();
+        assert_eq!(tables.len(), 1);
+        assert_eq!(
+            tables[0].to_string_table().to_csv().unwrap(),
+            "a,b\nc,c\nd,e\n"
+        );
+        assert_eq!(tables[0].row_groups()[0].section, Section::Body);
+        assert_eq!(tables[0].row_groups()[0].rows, 0..2);
+        assert!(
+            tables[0].to_string_table_with_header().rows()[0][0]
+                .unwrap()
+                .1
+        );
+
+        let xml = r#"<table><tr><td>1</td><td>2</td></tr></table>"#;
+        let package = sxd_document::parser::parse(xml).unwrap();
+        let document = package.as_document();
+        let tables = extract_table_nodes_to_table(document.root()).unwrap();
+        assert_eq!(tables[0].to_string_table().to_csv().unwrap(), "1,2\n");
+    }
+
+    #[test]
+    fn test_row_groups_keep_tfoot_last() {
+        let html = r#"
+        <html>
+            <body>
+                <table>
+                    <thead><tr><th>Item</th><th>Qty</th></tr></thead>
+                    <tfoot><tr><td>Total</td><td>3</td></tr></tfoot>
+                    <tbody><tr><td>a</td><td>1</td></tr></tbody>
+                    <tbody><tr><td>b</td><td>2</td></tr></tbody>
+                </table>
+            </body>
+        </html>
+        "#;
+        let package = sxd_html::parse_html(html);
+        let document = package.as_document();
+        let tables = extract_table_nodes_to_table(document.root()).unwrap();
+        let table = &tables[0];
+        assert_eq!(
+            table.to_string_table().to_csv().unwrap(),
+            "Item,Qty\na,1\nb,2\nTotal,3\n"
+        );
+        assert_eq!(
+            table.row_groups(),
+            [
+                RowGroup {
+                    section: Section::Head,
+                    index: 0,
+                    rows: 0..1,
+                },
+                RowGroup {
+                    section: Section::Body,
+                    index: 0,
+                    rows: 1..2,
+                },
+                RowGroup {
+                    section: Section::Body,
+                    index: 1,
+                    rows: 2..3,
+                },
+                RowGroup {
+                    section: Section::Foot,
+                    index: 0,
+                    rows: 3..4,
+                },
+            ]
+        );
+        let without_footer = table.filter_row_groups(|group| group.section != Section::Foot);
+        assert_eq!(
+            without_footer.to_string_table().to_csv().unwrap(),
+            "Item,Qty\na,1\nb,2\n"
+        );
+    }
+
     #[test]
     fn test_to_string_table_with_header_thead() {
         let html = r#"
@@ -586,8 +1102,9 @@ mod tests {
 
     #[test]
     fn test_rowspan_exceeds_actual_rows() {
-        // rowspan larger than the actual row count must be clamped to the remaining rows
-        // per HTML5 §4.9.11, not produce phantom rows
+        // Like browsers, the rowspan is clamped to the rows of its row group
+        // by default. The HTML table model instead grows the table to fit
+        // it, capped at 65534 rows.
         let html = r#"
         <html>
             <body>
@@ -601,16 +1118,50 @@ mod tests {
         "#;
         let result = extract_table_texts_from_document(html).unwrap();
         assert_eq!(result.len(), 1);
-        // table has exactly 3 rows, not 99999
-        assert_eq!(result[0].to_csv().unwrap(), "a,b\na,c\na,d\n");
+        assert_eq!(result[0].size(), (3, 2));
+        assert_eq!(result[0].cell(0, 0).unwrap().span.rowspan, 3);
+        let rows = result[0].rows();
+        assert_eq!(rows[2], [Some(&"a".to_string()), Some(&"d".to_string())]);
+
+        let package = sxd_html::parse_html(html);
+        let document = package.as_document();
+        let options = ExtractOptions::new().clamp_rowspans(false);
+        let tables = extract_table_nodes_to_table_with(document.root(), &options).unwrap();
+        let table = tables[0].to_string_table();
+        assert_eq!(table.size(), (65534, 2));
+        assert_eq!(table.row_groups()[0].rows, 0..65534);
+        assert_eq!(table.rows()[65533], [Some(&"a".to_string()), None]);
+    }
+
+    #[test]
+    fn test_span_values_follow_html_integer_parsing() {
+        let html = r#"
+        <table>
+            <tr><td colspan=" 2">a</td><td colspan="2px">b</td><td colspan="+1.5">c</td></tr>
+            <tr><td colspan="x2">d</td><td colspan="-2">e</td><td colspan="-0">f</td></tr>
+            <tr><td colspan="99999999999999999999999">g</td></tr>
+        </table>
+        "#;
+        let tables = extract_table_texts_from_document(html).unwrap();
+        let spans: Vec<Vec<usize>> = tables[0]
+            .rows()
+            .iter()
+            .enumerate()
+            .map(|(row, slots)| {
+                (0..slots.len())
+                    .filter_map(|col| tables[0].cell(row, col))
+                    .filter(|cell| cell.is_origin())
+                    .map(|cell| cell.span.colspan)
+                    .collect()
+            })
+            .collect();
+        assert_eq!(spans, [vec![2, 2, 1], vec![1, 1, 1], vec![1000]]);
     }
 
     #[test]
     fn test_rowspan_zero_multiple_in_same_row() {
-        // When a row has multiple rowspan=0 cells, only the first one spans to the
-        // end of the row group. Subsequent rowspan=0 cells are treated as rowspan=1
-        // to prevent state-machine ambiguity (multiple cells competing for the same
-        // row-group span).
+        // Every rowspan=0 cell grows downward to the end of its row group, so
+        // "c" is pushed past both of them.
         let html = r#"
         <html>
             <body>
@@ -623,9 +1174,6 @@ mod tests {
         "#;
         let result = extract_table_texts_from_document(html).unwrap();
         assert_eq!(result.len(), 1);
-        // "a" spans both rows (first rowspan=0 in the row).
-        // "b" appears only in row 0 (second rowspan=0 treated as rowspan=1).
-        // "c" fills row 1 at col 1.
-        assert_eq!(result[0].to_csv().unwrap(), "a,b\na,c\n");
+        assert_eq!(result[0].to_csv().unwrap(), "a,b,\na,b,c\n");
     }
 }
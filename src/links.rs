@@ -0,0 +1,430 @@
+use sxd_xpath::nodeset::Node;
+
+use crate::{
+    node_utils::descendant_elements,
+    table::Table,
+    text::{cell_text, TextOptions},
+};
+
+/// An `<a href>` element found in a cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    /// The `href` attribute, resolved against the base URL when there is one.
+    pub href: String,
+    /// The link text, extracted with the default [`TextOptions`].
+    pub text: String,
+    pub title: Option<String>,
+}
+
+/// An `<img src>` element found in a cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    /// The `src` attribute, resolved against the base URL when there is one.
+    pub src: String,
+    pub alt: Option<String>,
+}
+
+/// How [`Table::links`] and [`Table::images`] resolve relative URLs.
+///
+/// By default the document's `<base href>` is used when present, and URLs
+/// are returned as written otherwise. URLs are resolved with [`resolve_url`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkOptions {
+    base_url: Option<String>,
+    document_base: bool,
+}
+
+impl Default for LinkOptions {
+    fn default() -> Self {
+        Self {
+            base_url: None,
+            document_base: true,
+        }
+    }
+}
+
+impl LinkOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the URL the document was retrieved from. Relative URLs, including
+    /// a relative `<base href>`, are resolved against it.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Uses the first `<base href>` of the document as the base URL. Enabled
+    /// by default.
+    pub fn document_base(mut self, document_base: bool) -> Self {
+        self.document_base = document_base;
+        self
+    }
+
+    /// Returns the absolute base URL in effect for the document of `node`.
+    fn effective_base(&self, node: Node<'_>) -> Option<String> {
+        let document_base = self
+            .document_base
+            .then(|| find_base_href(node.document().root().into()))
+            .flatten();
+        match (document_base, &self.base_url) {
+            (Some(href), Some(base)) => resolve_url(base, &href),
+            (Some(href), None) => is_absolute(&href).then_some(href),
+            (None, base) => base.clone(),
+        }
+    }
+}
+
+impl Table<Node<'_>> {
+    /// Returns the `<a href>` elements of every cell in document order.
+    ///
+    /// ```
+    /// use sxd_html_table::{extract_table_nodes_to_table, links::LinkOptions};
+    ///
+    /// let package = sxd_html::parse_html(
+    ///     r#"<table><tr><td><a href="/docs" title="Docs">Read <b>more</b></a></td></tr></table>"#,
+    /// );
+    /// let tables = extract_table_nodes_to_table(package.as_document().root()).unwrap();
+    /// let links = tables[0].links(&LinkOptions::new().base_url("https://example.com/a/b"));
+    /// let link = &links.cell(0, 0).unwrap().value[0];
+    /// assert_eq!(link.href, "https://example.com/docs");
+    /// assert_eq!(link.text, "Read more");
+    /// assert_eq!(link.title.as_deref(), Some("Docs"));
+    /// ```
+    pub fn links(&self, options: &LinkOptions) -> Table<Vec<Link>> {
+        let base = self.base_for(options);
+        self.map(|_, _, node| cell_links(*node, base.as_deref()))
+    }
+
+    /// Returns the `<img src>` elements of every cell in document order.
+    pub fn images(&self, options: &LinkOptions) -> Table<Vec<Image>> {
+        let base = self.base_for(options);
+        self.map(|_, _, node| cell_images(*node, base.as_deref()))
+    }
+
+    fn base_for(&self, options: &LinkOptions) -> Option<String> {
+        match self.cells().next() {
+            Some(cell) => options.effective_base(*cell.value),
+            None => options.base_url.clone(),
+        }
+    }
+}
+
+/// Returns the `<a href>` descendants of `node`, with `href` resolved
+/// against `base` when given.
+pub fn cell_links(node: Node<'_>, base: Option<&str>) -> Vec<Link> {
+    descendant_elements(node, "a")
+        .into_iter()
+        .filter_map(|node| {
+            let element = node.element()?;
+            Some(Link {
+                href: resolve_or_keep(base, element.attribute_value("href")?),
+                text: cell_text(node, &TextOptions::default()),
+                title: element.attribute_value("title").map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+/// Returns the `<img src>` descendants of `node`, with `src` resolved against
+/// `base` when given.
+pub fn cell_images(node: Node<'_>, base: Option<&str>) -> Vec<Image> {
+    descendant_elements(node, "img")
+        .into_iter()
+        .filter_map(|node| {
+            let element = node.element()?;
+            Some(Image {
+                src: resolve_or_keep(base, element.attribute_value("src")?),
+                alt: element.attribute_value("alt").map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+fn find_base_href(node: Node<'_>) -> Option<String> {
+    descendant_elements(node, "base")
+        .into_iter()
+        .find_map(|node| node.element()?.attribute_value("href"))
+        .map(str::to_string)
+}
+
+fn resolve_or_keep(base: Option<&str>, reference: &str) -> String {
+    let reference = reference.trim();
+    base.and_then(|base| resolve_url(base, reference))
+        .unwrap_or_else(|| reference.to_string())
+}
+
+/// The five components of a URI reference (RFC 3986, appendix B).
+struct UriRef<'s> {
+    scheme: Option<&'s str>,
+    authority: Option<&'s str>,
+    path: &'s str,
+    query: Option<&'s str>,
+    fragment: Option<&'s str>,
+}
+
+impl<'s> UriRef<'s> {
+    fn parse(s: &'s str) -> Self {
+        let (s, fragment) = match s.split_once('#') {
+            Some((rest, fragment)) => (rest, Some(fragment)),
+            None => (s, None),
+        };
+        let (s, query) = match s.split_once('?') {
+            Some((rest, query)) => (rest, Some(query)),
+            None => (s, None),
+        };
+        let (scheme, s) = match s.split_once(':') {
+            Some((scheme, rest))
+                if scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && scheme
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.')) =>
+            {
+                (Some(scheme), rest)
+            }
+            _ => (None, s),
+        };
+        let (authority, path) = match s.strip_prefix("//") {
+            Some(rest) => {
+                let end = rest.find('/').unwrap_or(rest.len());
+                (Some(&rest[..end]), &rest[end..])
+            }
+            None => (None, s),
+        };
+        Self {
+            scheme,
+            authority,
+            path,
+            query,
+            fragment,
+        }
+    }
+}
+
+fn is_absolute(url: &str) -> bool {
+    UriRef::parse(url).scheme.is_some()
+}
+
+/// Resolves `reference` against the absolute URL `base` following
+/// RFC 3986, section 5.2.
+///
+/// Returns `None` if `base` has no scheme.
+///
+/// This is not the WHATWG URL parser browsers use: URLs are not normalized,
+/// so backslashes stay path characters, hosts keep their case, encoding and
+/// default port, and nothing is percent-encoded. The results match a
+/// browser's for the usual `http(s)` links, but may differ on such unusual
+/// input.
+///
+/// ```
+/// use sxd_html_table::links::resolve_url;
+///
+/// let base = "http://a/b/c/d;p?q";
+/// assert_eq!(resolve_url(base, "../g").as_deref(), Some("http://a/b/g"));
+/// assert_eq!(resolve_url(base, "?y").as_deref(), Some("http://a/b/c/d;p?y"));
+/// assert_eq!(resolve_url("docs/", "x"), None);
+/// ```
+pub fn resolve_url(base: &str, reference: &str) -> Option<String> {
+    let base = UriRef::parse(base);
+    let base_scheme = base.scheme?;
+    let r = UriRef::parse(reference);
+    let (scheme, authority, path, query);
+    if let Some(r_scheme) = r.scheme {
+        scheme = r_scheme;
+        authority = r.authority;
+        path = remove_dot_segments(r.path);
+        query = r.query;
+    } else {
+        scheme = base_scheme;
+        if r.authority.is_some() {
+            authority = r.authority;
+            path = remove_dot_segments(r.path);
+            query = r.query;
+        } else {
+            authority = base.authority;
+            if r.path.is_empty() {
+                path = base.path.to_string();
+                query = r.query.or(base.query);
+            } else {
+                path = if r.path.starts_with('/') {
+                    remove_dot_segments(r.path)
+                } else {
+                    remove_dot_segments(&merge(&base, r.path))
+                };
+                query = r.query;
+            }
+        }
+    }
+    let mut url = format!("{scheme}:");
+    if let Some(authority) = authority {
+        url.push_str("//");
+        url.push_str(authority);
+    }
+    url.push_str(&path);
+    if let Some(query) = query {
+        url.push('?');
+        url.push_str(query);
+    }
+    if let Some(fragment) = r.fragment {
+        url.push('#');
+        url.push_str(fragment);
+    }
+    Some(url)
+}
+
+fn merge(base: &UriRef<'_>, path: &str) -> String {
+    if base.authority.is_some() && base.path.is_empty() {
+        return format!("/{path}");
+    }
+    match base.path.rfind('/') {
+        Some(index) => format!("{}{path}", &base.path[..=index]),
+        None => path.to_string(),
+    }
+}
+
+fn remove_dot_segments(path: &str) -> String {
+    let mut input = path;
+    let mut output: Vec<&str> = vec![];
+    while !input.is_empty() {
+        if let Some(rest) = input
+            .strip_prefix("../")
+            .or_else(|| input.strip_prefix("./"))
+        {
+            input = rest;
+        } else if input.starts_with("/./") {
+            input = &input[2..];
+        } else if input == "/." {
+            input = "/";
+        } else if input.starts_with("/../") || input == "/.." {
+            input = if input == "/.." { "/" } else { &input[3..] };
+            output.pop();
+        } else if input == "." || input == ".." {
+            input = "";
+        } else {
+            let start = usize::from(input.starts_with('/'));
+            let end = input[start..].find('/').map_or(input.len(), |i| i + start);
+            output.push(&input[..end]);
+            input = &input[end..];
+        }
+    }
+    output.concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract_table_nodes_to_table;
+
+    #[test]
+    fn resolves_rfc_3986_examples() {
+        let base = "http://a/b/c/d;p?q";
+        let cases = [
+            ("g:h", "g:h"),
+            ("g", "http://a/b/c/g"),
+            ("./g", "http://a/b/c/g"),
+            ("g/", "http://a/b/c/g/"),
+            ("/g", "http://a/g"),
+            ("//g", "http://g"),
+            ("?y", "http://a/b/c/d;p?y"),
+            ("g?y", "http://a/b/c/g?y"),
+            ("#s", "http://a/b/c/d;p?q#s"),
+            ("g#s", "http://a/b/c/g#s"),
+            (";x", "http://a/b/c/;x"),
+            ("", "http://a/b/c/d;p?q"),
+            (".", "http://a/b/c/"),
+            ("./", "http://a/b/c/"),
+            ("..", "http://a/b/"),
+            ("../g", "http://a/b/g"),
+            ("../..", "http://a/"),
+            ("../../g", "http://a/g"),
+            ("../../../g", "http://a/g"),
+            ("/./g", "http://a/g"),
+            ("/../g", "http://a/g"),
+            ("g.", "http://a/b/c/g."),
+            ("..g", "http://a/b/c/..g"),
+            ("./../g", "http://a/b/g"),
+            ("g/./h", "http://a/b/c/g/h"),
+            ("g/../h", "http://a/b/c/h"),
+            ("g;x=1/../y", "http://a/b/c/y"),
+        ];
+        for (reference, expected) in cases {
+            assert_eq!(
+                resolve_url(base, reference).as_deref(),
+                Some(expected),
+                "{reference}"
+            );
+        }
+        assert_eq!(
+            resolve_url("https://example.com", "a").as_deref(),
+            Some("https://example.com/a")
+        );
+    }
+
+    #[test]
+    fn extracts_links_and_images_with_base() {
+        let html = r#"
+        <html>
+            <head><base href="/static/"></head>
+            <body>
+                <table>
+                    <tr>
+                        <td><a href="a.html" title="A">First</a> and <a href="https://x.org/">x</a></td>
+                        <td><img src="logo.png" alt="Logo"><img src="spacer.gif"><a name="top">no href</a></td>
+                    </tr>
+                </table>
+            </body>
+        </html>
+        "#;
+        let package = sxd_html::parse_html(html);
+        let document = package.as_document();
+        let tables = extract_table_nodes_to_table(document.root()).unwrap();
+        let table = &tables[0];
+
+        let links = table.links(&LinkOptions::new().base_url("https://example.com/page"));
+        assert_eq!(
+            links.cell(0, 0).unwrap().value,
+            &[
+                Link {
+                    href: "https://example.com/static/a.html".to_string(),
+                    text: "First".to_string(),
+                    title: Some("A".to_string()),
+                },
+                Link {
+                    href: "https://x.org/".to_string(),
+                    text: "x".to_string(),
+                    title: None,
+                },
+            ]
+        );
+        assert!(links.cell(0, 1).unwrap().value.is_empty());
+
+        let images = table.images(&LinkOptions::new().base_url("https://example.com/page"));
+        assert_eq!(
+            images.cell(0, 1).unwrap().value,
+            &[
+                Image {
+                    src: "https://example.com/static/logo.png".to_string(),
+                    alt: Some("Logo".to_string()),
+                },
+                Image {
+                    src: "https://example.com/static/spacer.gif".to_string(),
+                    alt: None,
+                },
+            ]
+        );
+
+        // A relative `<base href>` cannot be used on its own.
+        let images = table.images(&LinkOptions::new());
+        assert_eq!(images.cell(0, 1).unwrap().value[0].src, "logo.png");
+        let images = table.images(
+            &LinkOptions::new()
+                .base_url("https://example.com/page")
+                .document_base(false),
+        );
+        assert_eq!(
+            images.cell(0, 1).unwrap().value[0].src,
+            "https://example.com/logo.png"
+        );
+    }
+}
@@ -0,0 +1,364 @@
+use std::{fmt::Display, io::Write};
+
+use sxd_xpath::nodeset::Node;
+
+pub use crate::table::Alignment;
+use crate::{
+    element_utils,
+    records::{column_names, HeaderRows},
+    table::Table,
+    Error,
+};
+
+impl Alignment {
+    fn delimiter(self, width: usize) -> String {
+        let dashes = "-".repeat(width);
+        match self {
+            Self::None => dashes,
+            Self::Left => format!(":{}", &dashes[1..]),
+            Self::Center => format!(":{}:", &dashes[2..]),
+            Self::Right => format!("{}:", &dashes[1..]),
+        }
+    }
+}
+
+/// Options for [`Table::write_markdown_with`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownOptions {
+    alignments: Vec<Alignment>,
+    header_rows: HeaderRows,
+    pad: bool,
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        Self {
+            alignments: vec![],
+            header_rows: HeaderRows::Detect,
+            pad: true,
+        }
+    }
+}
+
+impl MarkdownOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the alignment of each column. Columns without an entry are not
+    /// aligned.
+    pub fn alignments(mut self, alignments: Vec<Alignment>) -> Self {
+        self.alignments = alignments;
+        self
+    }
+
+    /// Sets which leading rows form the Markdown header row. Defaults to
+    /// [`HeaderRows::Detect`], as for [`Table::to_records`].
+    ///
+    /// Multi-row headers are joined like [`Records::columns`]. Without
+    /// header rows, an empty header row is written.
+    ///
+    /// [`Records::columns`]: crate::Records::columns
+    pub fn header_rows(mut self, header_rows: HeaderRows) -> Self {
+        self.header_rows = header_rows;
+        self
+    }
+
+    /// Pads cells with spaces so the columns line up. Enabled by default.
+    pub fn pad(mut self, pad: bool) -> Self {
+        self.pad = pad;
+        self
+    }
+}
+
+impl<T> Table<T>
+where
+    T: Display,
+{
+    /// Writes the table as a GitHub-flavored Markdown pipe table with the
+    /// default [`MarkdownOptions`].
+    pub fn write_markdown(&self, writer: &mut impl Write) -> Result<(), Error> {
+        self.write_markdown_with(writer, &MarkdownOptions::default())
+    }
+
+    /// Writes the table as a GitHub-flavored Markdown pipe table.
+    ///
+    /// The header rows chosen by [`MarkdownOptions::header_rows`] become the
+    /// Markdown header row. `\`, `|`, `&`, `<` and `>` in cells are escaped
+    /// and line breaks are written as `<br>`. Empty slots are written as
+    /// empty cells, and a table without rows or columns produces no output.
+    pub fn write_markdown_with(
+        &self,
+        writer: &mut impl Write,
+        options: &MarkdownOptions,
+    ) -> Result<(), Error> {
+        let (rows, cols) = self.size();
+        if rows == 0 || cols == 0 {
+            return Ok(());
+        }
+        let header_rows = self.header_row_count(options.header_rows);
+        let header: Vec<String> = if header_rows == 0 {
+            vec![String::new(); cols]
+        } else {
+            column_names(self, header_rows, ToString::to_string)
+                .iter()
+                .map(|name| escape(name))
+                .collect()
+        };
+        let texts: Vec<Vec<String>> = self.rows()[header_rows..]
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| cell.map_or_else(String::new, |item| escape(&item.to_string())))
+                    .collect()
+            })
+            .collect();
+        let alignment = |col: usize| options.alignments.get(col).copied().unwrap_or_default();
+        let widths: Vec<usize> = (0..cols)
+            .map(|col| {
+                if !options.pad {
+                    return 3;
+                }
+                std::iter::once(&header)
+                    .chain(&texts)
+                    .map(|row| row[col].chars().count())
+                    .max()
+                    .unwrap_or(0)
+                    .max(3)
+            })
+            .collect();
+        write_row(writer, &header, &widths, options.pad, alignment)?;
+        let delimiters: Vec<String> = (0..cols)
+            .map(|col| alignment(col).delimiter(widths[col]))
+            .collect();
+        write_row(writer, &delimiters, &widths, false, alignment)?;
+        for row in &texts {
+            write_row(writer, row, &widths, options.pad, alignment)?;
+        }
+        Ok(())
+    }
+
+    pub fn to_markdown(&self) -> Result<String, Error> {
+        self.to_markdown_with(&MarkdownOptions::default())
+    }
+
+    pub fn to_markdown_with(&self, options: &MarkdownOptions) -> Result<String, Error> {
+        let mut buf = Vec::new();
+        self.write_markdown_with(&mut buf, options)?;
+        crate::utf8_output(buf)
+    }
+}
+
+impl Table<Node<'_>> {
+    /// Derives an alignment for every column from the `style="text-align:
+    /// ..."` or `align` attributes of its cells.
+    ///
+    /// The header rows, detected as for [`Table::to_records`], are ignored
+    /// unless no other cell in the column carries an alignment. When cells
+    /// disagree, the most common alignment wins.
+    pub fn column_alignments(&self) -> Vec<Alignment> {
+        let (_, cols) = self.size();
+        let rows = self.rows();
+        let (header, body) = rows.split_at(self.header_row_count(HeaderRows::Detect));
+        (0..cols)
+            .map(|col| {
+                most_common_alignment(body.iter().map(|row| row[col]))
+                    .or_else(|| most_common_alignment(header.iter().map(|row| row[col])))
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+}
+
+fn most_common_alignment<'a, 'n: 'a>(
+    cells: impl Iterator<Item = Option<&'a Node<'n>>>,
+) -> Option<Alignment> {
+    let mut counts: Vec<(Alignment, usize)> = vec![];
+    for alignment in cells
+        .flatten()
+        .filter_map(|node| node.element())
+        .filter_map(element_utils::extract_text_align)
+    {
+        match counts.iter_mut().find(|(seen, _)| *seen == alignment) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((alignment, 1)),
+        }
+    }
+    // `max_by_key` keeps the last maximum, so walk in reverse to let the
+    // alignment seen first win ties.
+    counts
+        .into_iter()
+        .rev()
+        .max_by_key(|(_, count)| *count)
+        .map(|(alignment, _)| alignment)
+}
+
+fn write_row(
+    writer: &mut impl Write,
+    cells: &[String],
+    widths: &[usize],
+    pad: bool,
+    alignment: impl Fn(usize) -> Alignment,
+) -> std::io::Result<()> {
+    writer.write_all(b"|")?;
+    for (col, text) in cells.iter().enumerate() {
+        let fill = if pad {
+            widths[col].saturating_sub(text.chars().count())
+        } else {
+            0
+        };
+        let (left, right) = match alignment(col) {
+            Alignment::Right => (fill, 0),
+            Alignment::Center => (fill / 2, fill - fill / 2),
+            Alignment::None | Alignment::Left => (0, fill),
+        };
+        write!(writer, " {}{text}{} |", " ".repeat(left), " ".repeat(right))?;
+    }
+    writer.write_all(b"\n")
+}
+
+/// Escapes cell text for a pipe table. `&`, `<` and `>` become character
+/// references first, so text such as `<script>` is not rendered as HTML and
+/// only the `<br>` written for line breaks is. Backslashes are doubled before
+/// pipes are escaped, so a `\` in the text cannot escape the backslash added
+/// in front of a following `|` and end the cell.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace(['\n', '\r'], "<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract_table_nodes_to_table;
+
+    #[test]
+    fn writes_padded_pipe_table_with_escapes() {
+        let mut table = Table::new((3, 2));
+        table.set(0, 0, "Name".to_string());
+        table.set(0, 1, "Note".to_string());
+        table.set(1, 0, "Alice".to_string());
+        table.set(1, 1, "a|<b>".to_string());
+        table.set(2, 0, "Bob".to_string());
+        table.set(2, 1, "line 1\nline 2".to_string());
+        table.set_header(0, 0, true);
+        table.set_header(0, 1, true);
+
+        assert_eq!(
+            table.to_markdown().unwrap(),
+            "| Name  | Note             |\n\
+             | ----- | ---------------- |\n\
+             | Alice | a\\|&lt;b&gt;     |\n\
+             | Bob   | line 1<br>line 2 |\n"
+        );
+        assert_eq!(
+            table
+                .to_markdown_with(&MarkdownOptions::new().pad(false))
+                .unwrap(),
+            "| Name | Note |\n| --- | --- |\n| Alice | a\\|&lt;b&gt; |\n| Bob | line 1<br>line 2 |\n"
+        );
+        assert_eq!(Table::<String>::new((0, 0)).to_markdown().unwrap(), "");
+        assert_eq!(Table::<String>::new((2, 0)).to_markdown().unwrap(), "");
+    }
+
+    #[test]
+    fn escaped_backslashes_do_not_split_cells() {
+        let mut table = Table::new((2, 1));
+        table.set(0, 0, "Path".to_string());
+        table.set(1, 0, r"a\|b".to_string());
+        table.set_header(0, 0, true);
+
+        let markdown = table
+            .to_markdown_with(&MarkdownOptions::new().pad(false))
+            .unwrap();
+        assert_eq!(markdown, "| Path |\n| --- |\n| a\\\\\\|b |\n");
+        // Every pipe in the body row but the outer two is preceded by an odd
+        // number of backslashes, so it stays inside the cell.
+        let row = markdown.lines().last().unwrap();
+        let delimiters = row
+            .char_indices()
+            .filter(|&(i, c)| {
+                c == '|' && row[..i].chars().rev().take_while(|&c| c == '\\').count() % 2 == 0
+            })
+            .count();
+        assert_eq!(delimiters, 2);
+    }
+
+    #[test]
+    fn writes_detected_header_rows() {
+        let html = r#"
+        <table>
+            <thead>
+                <tr><th rowspan="2">Item</th><th colspan="2">2023</th></tr>
+                <tr><th>Q1</th><th>Q2</th></tr>
+            </thead>
+            <tr><td>a</td><td>1</td><td>2</td></tr>
+        </table>
+        <table><tr><td>a</td><td>1</td></tr></table>
+        "#;
+        let package = sxd_html::parse_html(html);
+        let document = package.as_document();
+        let tables = extract_table_nodes_to_table(document.root()).unwrap();
+        assert_eq!(
+            tables[0].to_string_table().to_markdown().unwrap(),
+            "| Item | 2023 / Q1 | 2023 / Q2 |\n\
+             | ---- | --------- | --------- |\n\
+             | a    | 1         | 2         |\n"
+        );
+        assert_eq!(
+            tables[1].to_string_table().to_markdown().unwrap(),
+            "|     |     |\n| --- | --- |\n| a   | 1   |\n"
+        );
+        let options = MarkdownOptions::new().header_rows(HeaderRows::Count(1));
+        assert_eq!(
+            tables[1]
+                .to_string_table()
+                .to_markdown_with(&options)
+                .unwrap(),
+            "| a   | 1   |\n| --- | --- |\n"
+        );
+    }
+
+    #[test]
+    fn aligns_columns_from_cell_attributes() {
+        let html = r#"
+        <table>
+            <tr><th>Item</th><th>Qty</th><th>Note</th></tr>
+            <tr><td align="center">a</td><td style="color: red; text-align: right">10</td><td></td></tr>
+            <tr><td>b</td><td align="left" style="TEXT-ALIGN:Right">200</td><td></td></tr>
+        </table>
+        "#;
+        let package = sxd_html::parse_html(html);
+        let document = package.as_document();
+        let tables = extract_table_nodes_to_table(document.root()).unwrap();
+        let alignments = tables[0].column_alignments();
+        assert_eq!(
+            alignments,
+            [Alignment::Center, Alignment::Right, Alignment::None]
+        );
+
+        let options = MarkdownOptions::new().alignments(alignments);
+        assert_eq!(
+            tables[0]
+                .to_string_table()
+                .to_markdown_with(&options)
+                .unwrap(),
+            "| Item | Qty | Note |\n\
+             | :--: | --: | ---- |\n\
+             |  a   |  10 |      |\n\
+             |  b   | 200 |      |\n"
+        );
+
+        // Without header cells every row counts.
+        let package = sxd_html::parse_html(
+            r#"<table><tr><td align="right">1</td></tr><tr><td align="center">2</td></tr></table>"#,
+        );
+        let tables = extract_table_nodes_to_table(package.as_document().root()).unwrap();
+        assert_eq!(tables[0].column_alignments(), [Alignment::Right]);
+    }
+}
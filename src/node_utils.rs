@@ -1,12 +1,30 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, ops::Range};
 
+use sxd_document::dom::Element;
 use sxd_xpath::{nodeset::Node, Context, Factory, Value};
 
-use crate::{element_utils, table::Table, Error};
+use crate::{
+    element_utils,
+    extract::{Diagnostic, DiagnosticKind, ExtractOptions, Extraction, LimitAction},
+    table::{CellSpan, ColumnDef, ColumnGroup, Section, Table, TableInfo},
+    Error, Limit, Location,
+};
 
-pub(crate) const MAX_TABLE_COLUMNS: usize = 1000;
+impl<'a> Table<Node<'a>> {
+    /// Returns the `<table>` element the table was extracted from, found as
+    /// the nearest `<table>` ancestor of its first cell.
+    ///
+    /// Derived tables such as those from [`Table::filter_row_groups`] or
+    /// [`Table::fill_spans`] find the same element as long as they keep a
+    /// cell; tables without cells have none.
+    pub fn source_node(&self) -> Option<Node<'a>> {
+        let cell = self.cells().next()?;
+        std::iter::successors(cell.value.parent(), Node::parent).find(|node| {
+            node.element()
+                .is_some_and(|element| element_utils::is_html_element(element, "table"))
+        })
+    }
 
-impl Table<Node<'_>> {
     pub fn to_string_table(&self) -> Table<String> {
         self.map(|_, _, node| node.string_value())
     }
@@ -16,7 +34,7 @@ impl Table<Node<'_>> {
             let Some(element) = node.element() else {
                 return (node.string_value(), false);
             };
-            let is_header = element.name() == "th".into();
+            let is_header = element_utils::is_html_element(element, "th");
             (node.string_value(), is_header)
         })
     }
@@ -25,30 +43,336 @@ impl Table<Node<'_>> {
 struct TableSupport<'a>(Node<'a>);
 
 impl<'a> TableSupport<'a> {
-    fn tr_nodes(&self) -> Result<Vec<Node<'a>>, Error> {
-        let tr_nodes = match evaluate_xpath_node(self.0, "./thead/tr|./tbody/tr|./tfoot/tr") {
-            Ok(Value::Nodeset(tr_nodes)) => tr_nodes,
-            _ => {
-                return Err(Error::InvalidDocument(
-                    "XPath ./tbody/tr did not return a nodeset",
-                ))
+    /// Returns the `<tr>` rows of each row group, with `<tfoot>` groups moved
+    /// after the head and body groups.
+    ///
+    /// Consecutive `<tr>` elements that are direct children of `<table>`, as
+    /// found in XHTML or hand-built documents without an implicit `<tbody>`,
+    /// form a body group of their own.
+    fn row_groups(&self) -> Vec<(Section, Vec<Node<'a>>)> {
+        let mut groups: Vec<(Section, Vec<Node<'a>>)> = vec![];
+        let mut foot_groups = vec![];
+        let mut direct_rows = vec![];
+        for child in self.0.children() {
+            let Some(element) = child.element() else {
+                continue;
+            };
+            if element_utils::is_html_element(element, "tr") {
+                direct_rows.push(child);
+                continue;
+            }
+            let section = if element_utils::is_html_element(element, "thead") {
+                Section::Head
+            } else if element_utils::is_html_element(element, "tbody") {
+                Section::Body
+            } else if element_utils::is_html_element(element, "tfoot") {
+                Section::Foot
+            } else {
+                continue;
+            };
+            if !direct_rows.is_empty() {
+                groups.push((Section::Body, std::mem::take(&mut direct_rows)));
+            }
+            let rows = child_elements(child, "tr");
+            if section == Section::Foot {
+                foot_groups.push((section, rows));
+            } else {
+                groups.push((section, rows));
+            }
+        }
+        if !direct_rows.is_empty() {
+            groups.push((Section::Body, direct_rows));
+        }
+        groups.extend(foot_groups);
+        groups
+    }
+
+    fn info(&self) -> TableInfo {
+        let attribute = |name: &str| {
+            self.0
+                .element()
+                .and_then(|element| element.attribute_value(name))
+                .map(str::to_string)
+        };
+        TableInfo {
+            caption: caption_text(self.0),
+            id: attribute("id"),
+            classes: self
+                .0
+                .element()
+                .map(element_utils::extract_classes)
+                .unwrap_or_default(),
+            summary: attribute("summary"),
+            aria_label: attribute("aria-label"),
+            column_groups: self.column_groups(),
+        }
+    }
+
+    fn column_groups(&self) -> Vec<ColumnGroup> {
+        let mut groups = vec![];
+        let mut direct_cols = vec![];
+        for child in self.0.children() {
+            let Some(element) = child.element() else {
+                continue;
+            };
+            if element_utils::is_html_element(element, "col") {
+                direct_cols.push(column_def(element));
+                continue;
+            }
+            if !element_utils::is_html_element(element, "colgroup") {
+                continue;
+            }
+            if !direct_cols.is_empty() {
+                groups.push(column_group(None, std::mem::take(&mut direct_cols)));
+            }
+            let cols = child_elements(child, "col")
+                .into_iter()
+                .filter_map(|col| col.element())
+                .map(column_def)
+                .collect();
+            groups.push(column_group(Some(element), cols));
+        }
+        if !direct_cols.is_empty() {
+            groups.push(column_group(None, direct_cols));
+        }
+        groups
+    }
+
+    /// Returns the number of columns defined by the `<colgroup>` and
+    /// `<col>` elements that precede the first row, which the table is at
+    /// least as wide as. Columns are counted as [`Self::column_groups`] does.
+    fn leading_column_count(&self) -> usize {
+        let mut count = 0;
+        for child in self.0.children() {
+            let Some(element) = child.element() else {
+                continue;
+            };
+            if ["thead", "tbody", "tfoot", "tr"]
+                .iter()
+                .any(|name| element_utils::is_html_element(element, name))
+            {
+                break;
             }
+            if element_utils::is_html_element(element, "col") {
+                count += element_utils::extract_col_span(element);
+                continue;
+            }
+            if !element_utils::is_html_element(element, "colgroup") {
+                continue;
+            }
+            let cols = child_elements(child, "col");
+            count += if cols.is_empty() {
+                element_utils::extract_col_span(element)
+            } else {
+                cols.iter()
+                    .filter_map(|col| col.element())
+                    .map(element_utils::extract_col_span)
+                    .sum()
+            };
+        }
+        count
+    }
+
+    fn td_nodes(&self, tr: Node<'a>) -> Vec<(Node<'a>, Element<'a>)> {
+        tr.children()
+            .into_iter()
+            .filter_map(|cell| {
+                let element = cell.element().filter(|&element| {
+                    element_utils::is_html_element(element, "td")
+                        || element_utils::is_html_element(element, "th")
+                })?;
+                Some((cell, element))
+            })
+            .collect()
+    }
+}
+
+fn column_def(element: sxd_document::dom::Element) -> ColumnDef {
+    let attribute = |name| element.attribute_value(name).map(str::to_string);
+    ColumnDef {
+        span: element_utils::extract_col_span(element),
+        id: attribute("id"),
+        classes: element_utils::extract_classes(element),
+        width: attribute("width"),
+        style: attribute("style"),
+        hidden: element_utils::is_hidden(element),
+    }
+}
+
+fn column_group(element: Option<sxd_document::dom::Element>, cols: Vec<ColumnDef>) -> ColumnGroup {
+    let Some(element) = element else {
+        return ColumnGroup {
+            span: cols.iter().map(|col| col.span).sum(),
+            cols,
+            ..ColumnGroup::default()
+        };
+    };
+    let def = column_def(element);
+    ColumnGroup {
+        span: if cols.is_empty() {
+            def.span
+        } else {
+            cols.iter().map(|col| col.span).sum()
+        },
+        id: def.id,
+        classes: def.classes,
+        width: def.width,
+        style: def.style,
+        hidden: def.hidden,
+        cols,
+    }
+}
+
+/// Returns the whitespace-normalized text of the first `<caption>` child of
+/// a table.
+pub(crate) fn caption_text(table: Node<'_>) -> Option<String> {
+    let caption = child_elements(table, "caption").into_iter().next()?;
+    Some(
+        caption
+            .string_value()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+fn child_elements<'a>(node: Node<'a>, local_name: &str) -> Vec<Node<'a>> {
+    node.children()
+        .into_iter()
+        .filter(|child| {
+            child
+                .element()
+                .is_some_and(|element| element_utils::is_html_element(element, local_name))
+        })
+        .collect()
+}
+
+/// Returns every `<table>` element below `node`, nested ones included, in
+/// document order.
+pub(crate) fn all_table_nodes(node: Node<'_>) -> Vec<Node<'_>> {
+    descendant_elements(node, "table")
+}
+
+/// Returns every HTML element named `local_name` below `node`, in document
+/// order.
+pub(crate) fn descendant_elements<'a>(node: Node<'a>, local_name: &str) -> Vec<Node<'a>> {
+    let mut elements = vec![];
+    let mut stack: Vec<_> = node.children().into_iter().rev().collect();
+    while let Some(node) = stack.pop() {
+        if node
+            .element()
+            .is_some_and(|element| element_utils::is_html_element(element, local_name))
+        {
+            elements.push(node);
+        }
+        stack.extend(node.children().into_iter().rev());
+    }
+    elements
+}
+
+/// Returns the `<table>` elements below `node` that are not nested in
+/// another table below `node`, in document order.
+pub(crate) fn child_table_nodes(node: Node<'_>) -> Vec<Node<'_>> {
+    let mut tables = vec![];
+    let mut stack: Vec<_> = node.children().into_iter().rev().collect();
+    while let Some(node) = stack.pop() {
+        let Some(element) = node.element() else {
+            continue;
         };
-        Ok(tr_nodes.document_order())
+        if element_utils::is_html_element(element, "table") {
+            tables.push(node);
+        } else {
+            stack.extend(node.children().into_iter().rev());
+        }
+    }
+    tables
+}
+
+/// Returns an XPath-like path locating `node` from the document root, such
+/// as `/html/body/table[2]/tbody/tr[3]/td`.
+///
+/// Steps use local names; a position is added when the parent has several
+/// elements of the same name.
+pub fn node_path(node: Node<'_>) -> String {
+    let path = NodePaths::default().path(node);
+    if path.is_empty() {
+        return "/".to_string();
     }
-    fn td_nodes(&self, tr: Node<'a>) -> Result<Vec<Node<'a>>, Error> {
-        let td_nodes = match evaluate_xpath_node(tr, "./td|./th") {
-            Ok(Value::Nodeset(td_nodes)) => td_nodes,
-            _ => {
-                return Err(Error::InvalidDocument(
-                    "XPath ./td|./th did not return a nodeset",
-                ))
+    path
+}
+
+/// Memoized [`node_path`] results.
+///
+/// The steps of all children of a parent are computed together, so locating
+/// every cell of a table takes time linear in its size rather than a scan of
+/// the sibling rows per cell.
+#[derive(Default)]
+struct NodePaths<'a> {
+    paths: HashMap<Node<'a>, String>,
+}
+
+impl<'a> NodePaths<'a> {
+    /// Returns the path of `node`, empty for the document root.
+    fn path(&mut self, node: Node<'a>) -> String {
+        // The ancestors, innermost first, up to the first one with a known
+        // path.
+        let mut pending = vec![];
+        let mut current = node;
+        let mut path = loop {
+            if let Some(path) = self.paths.get(&current) {
+                break path.clone();
+            }
+            match current.parent() {
+                Some(parent) => {
+                    pending.push(current);
+                    current = parent;
+                }
+                None => break String::new(),
+            }
+        };
+        for node in pending.into_iter().rev() {
+            if node.element().is_some() {
+                path = self.step(node, &path);
             }
+        }
+        path
+    }
+
+    /// Records the paths of every element child of the parent of `node`,
+    /// whose path is `parent_path`, and returns the path of `node`.
+    fn step(&mut self, node: Node<'a>, parent_path: &str) -> String {
+        let Some(parent) = node.parent() else {
+            return parent_path.to_string();
         };
-        Ok(td_nodes.document_order())
+        let children: Vec<_> = parent
+            .children()
+            .into_iter()
+            .filter_map(|child| Some((child, child.element()?.name().local_part())))
+            .collect();
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for (_, name) in &children {
+            *counts.entry(name).or_default() += 1;
+        }
+        let mut positions: HashMap<&str, usize> = HashMap::new();
+        for (child, name) in children {
+            let step = if counts[name] > 1 {
+                let position = positions.entry(name).or_default();
+                *position += 1;
+                format!("{name}[{position}]")
+            } else {
+                name.to_string()
+            };
+            self.paths.insert(child, format!("{parent_path}/{step}"));
+        }
+        self.paths[&node].clone()
     }
 }
 
+/// Evaluates `expr` with `node` as the context node.
+///
+/// The expression is compiled on every call. Use
+/// [`XPathEvaluator`](crate::xpath::XPathEvaluator) to evaluate the same
+/// expressions repeatedly.
 pub fn evaluate_xpath_node<'a>(
     node: impl Into<Node<'a>>,
     expr: &str,
@@ -62,100 +386,729 @@ pub fn evaluate_xpath_node<'a>(
         .map_err(Into::into)
 }
 
-fn extract_table_nodes<'a>(node: impl Into<Node<'a>>) -> Result<Vec<Node<'a>>, Error> {
-    let val = evaluate_xpath_node(node, "//table[not(ancestor::table)]").map_err(Error::from)?;
-    let Value::Nodeset(table_nodes) = val else {
-        unreachable!("//table XPath always returns a Nodeset");
-    };
-    let nodes = table_nodes.document_order();
+/// Returns the top-level tables of the document `node` belongs to.
+///
+/// Like an absolute `//table` XPath, the whole document is searched
+/// regardless of which of its nodes is passed.
+pub(crate) fn extract_table_nodes<'a>(node: impl Into<Node<'a>>) -> Result<Vec<Node<'a>>, Error> {
+    let root = Node::from(node.into().document().root());
+    let nodes = child_table_nodes(root);
     if nodes.is_empty() {
-        return Err(Error::TableNotFound);
+        return Err(Error::TableNotFound { criterion: None });
+    }
+    Ok(nodes)
+}
+
+/// Returns the top-level tables of the document `node` belongs to, dropping
+/// those past the table limit of `options` or failing, depending on its
+/// [`LimitAction`].
+pub(crate) fn limited_table_nodes<'a>(
+    node: impl Into<Node<'a>>,
+    options: &ExtractOptions,
+) -> Result<Vec<Node<'a>>, Error> {
+    let mut nodes = extract_table_nodes(node)?;
+    if nodes.len() > options.max_tables {
+        if options.on_limit == LimitAction::Error {
+            return Err(limit_exceeded(
+                Limit::Tables,
+                options.max_tables,
+                nodes.len(),
+                Location {
+                    table: Some(options.max_tables),
+                    path: node_path(nodes[options.max_tables]),
+                    ..Location::default()
+                },
+            ));
+        }
+        nodes.truncate(options.max_tables);
     }
     Ok(nodes)
 }
 
 pub fn extract_table_nodes_to_table<'a>(
     node: impl Into<Node<'a>>,
+) -> Result<Vec<Table<Node<'a>>>, Error> {
+    extract_table_nodes_to_table_with(node, &ExtractOptions::default())
+}
+
+/// Extracts the top-level tables of the document `node` belongs to within
+/// the limits of `options`.
+///
+/// Tables left out with [`LimitAction::Skip`] are missing from the result,
+/// so the indices of the following tables shift.
+pub fn extract_table_nodes_to_table_with<'a>(
+    node: impl Into<Node<'a>>,
+    options: &ExtractOptions,
 ) -> Result<Vec<Table<Node<'a>>>, Error> {
     let mut tables = vec![];
-    for node in extract_table_nodes(node)? {
-        tables.push(node_to_table(node)?);
+    for (index, node) in limited_table_nodes(node, options)?.into_iter().enumerate() {
+        if let Some(table) = form_table(node, options).map_err(|err| err.in_table(index))? {
+            tables.push(table);
+        }
     }
     Ok(tables)
 }
 
-fn node_to_table<'a>(node: impl Into<Node<'a>>) -> Result<Table<Node<'a>>, Error> {
-    let mut map: HashMap<(usize, usize), Node> = HashMap::new();
-    let t = TableSupport(node.into());
-    let tr_nodes = t.tr_nodes()?;
-    for (row_index, tr_node) in tr_nodes.iter().enumerate() {
-        let mut rowspan_zero_seen = false;
-        for td_node in t.td_nodes(*tr_node)? {
-            let mut col_index = 0;
-            let Some(element) = td_node.element() else {
-                return Err(Error::InvalidDocument(
-                    "td/th node could not be cast to an element",
-                ));
+/// Extracts every top-level table of the document `node` belongs to that
+/// can be built, reporting what was repaired or left out as diagnostics
+/// instead of failing.
+///
+/// Tables exceeding a limit are truncated with [`LimitAction::Truncate`] and
+/// skipped otherwise; tables past the table limit are always dropped. Fails
+/// only when the document has no tables.
+///
+/// ```
+/// use sxd_html_table::{extract_table_nodes_to_table_lenient, ExtractOptions};
+///
+/// let package = sxd_html::parse_html(
+///     r#"<table><tr><td colspan="x">a</td><td rowspan="99999">b</td></tr></table>
+///        <table><tr><td colspan="5">wide</td></tr></table>"#,
+/// );
+/// let options = ExtractOptions::new().max_columns(4);
+/// let extraction =
+///     extract_table_nodes_to_table_lenient(package.as_document().root(), &options).unwrap();
+/// assert_eq!(extraction.tables.len(), 1);
+/// let messages: Vec<String> = extraction.diagnostics.iter().map(ToString::to_string).collect();
+/// assert_eq!(
+///     messages,
+///     [
+///         r#"non-numeric colspan ignored (table 0, row 0, column 0, colspan="x", at /html/body/table[1]/tbody/tr/td[1])"#,
+///         r#"rowspan clamped to 1 (table 0, row 0, column 1, rowspan="99999", at /html/body/table[1]/tbody/tr/td[2])"#,
+///         r#"table skipped, columns limit exceeded: 5 > 4 (table 1, row 0, column 0, colspan="5", at /html/body/table[2]/tbody/tr/td)"#,
+///     ]
+/// );
+/// ```
+pub fn extract_table_nodes_to_table_lenient<'a>(
+    node: impl Into<Node<'a>>,
+    options: &ExtractOptions,
+) -> Result<Extraction<'a>, Error> {
+    let mut nodes = extract_table_nodes(node)?;
+    let mut diagnostics = vec![];
+    if nodes.len() > options.max_tables {
+        diagnostics.push(Diagnostic {
+            kind: DiagnosticKind::Truncated {
+                limit: Limit::Tables,
+                max: options.max_tables,
+                actual: nodes.len(),
+            },
+            location: Location {
+                table: Some(options.max_tables),
+                path: node_path(nodes[options.max_tables]),
+                ..Location::default()
+            },
+        });
+        nodes.truncate(options.max_tables);
+    }
+    let mut tables = vec![];
+    for (index, node) in nodes.into_iter().enumerate() {
+        match form(node, options, true) {
+            Ok((table, table_diagnostics)) => {
+                tables.push(table);
+                diagnostics.extend(table_diagnostics.into_iter().map(|mut diagnostic| {
+                    diagnostic.location.table = Some(index);
+                    diagnostic
+                }));
+            }
+            Err(Error::LimitExceeded {
+                limit,
+                max,
+                actual,
+                mut location,
+            }) => {
+                location.table = Some(index);
+                diagnostics.push(Diagnostic {
+                    kind: DiagnosticKind::TableSkipped { limit, max, actual },
+                    location: *location,
+                });
+            }
+            Err(err) => return Err(err.in_table(index)),
+        }
+    }
+    Ok(Extraction {
+        tables,
+        diagnostics,
+    })
+}
+
+/// Validates every top-level table of the document `node` belongs to in
+/// [strict](ExtractOptions::strict) mode, returning the findings without
+/// failing. This makes a standalone HTML table linter.
+///
+/// ```
+/// use sxd_html_table::{validate_tables, ExtractOptions};
+///
+/// let package = sxd_html::parse_html(
+///     r#"<table>
+///          <tbody><tr><td rowspan="+2">a</td><td>b</td></tr></tbody>
+///          <tbody><tr><td>c</td></tr></tbody>
+///        </table>"#,
+/// );
+/// let findings = validate_tables(package.as_document().root(), &ExtractOptions::new()).unwrap();
+/// let messages: Vec<String> = findings.iter().map(ToString::to_string).collect();
+/// assert_eq!(
+///     messages,
+///     [
+///         r#"rowspan is not a valid non-negative integer (table 0, row 0, column 0, rowspan="+2", at /html/body/table/tbody[1]/tr/td[1])"#,
+///         r#"rowspan 2 reaches 1 row past the end of its row group (table 0, row 0, column 0, rowspan="+2", at /html/body/table/tbody[1]/tr/td[1])"#,
+///         "row covers 1 of 2 columns (table 0, row 1, at /html/body/table/tbody[2]/tr)",
+///     ]
+/// );
+/// ```
+pub fn validate_tables<'a>(
+    node: impl Into<Node<'a>>,
+    options: &ExtractOptions,
+) -> Result<Vec<Diagnostic>, Error> {
+    let options = options.clone().strict(true);
+    Ok(extract_table_nodes_to_table_lenient(node, &options)?.diagnostics)
+}
+
+/// Forms the table of `node`, returning `None` when it exceeds a limit and
+/// `options` skips such tables.
+pub(crate) fn form_table<'a>(
+    node: Node<'a>,
+    options: &ExtractOptions,
+) -> Result<Option<Table<Node<'a>>>, Error> {
+    match node_to_table_with(node, options) {
+        Err(Error::LimitExceeded { .. }) if options.on_limit == LimitAction::Skip => Ok(None),
+        result => result.map(Some),
+    }
+}
+
+/// Dense occupancy grid used while forming a table. Each slot holds the
+/// index of the cell covering it; rows grow to the right as cells are
+/// placed.
+struct SlotGrid {
+    rows: Vec<Vec<Option<usize>>>,
+}
+
+impl SlotGrid {
+    /// Returns the first slot of `row` at or after `col` that no cell covers.
+    fn first_free(&self, row: usize, mut col: usize) -> usize {
+        let slots = &self.rows[row];
+        while slots.get(col).is_some_and(Option::is_some) {
+            col += 1;
+        }
+        col
+    }
+
+    /// Marks every slot covered by `span` as belonging to `cell`, replacing
+    /// any cell placed there before. Returns the first other cell replaced.
+    fn cover(&mut self, span: CellSpan, cell: usize) -> Option<usize> {
+        let end_row = span.row + span.rowspan;
+        if self.rows.len() < end_row {
+            self.rows.resize(end_row, vec![]);
+        }
+        let end_col = span.col + span.colspan;
+        let mut replaced = None;
+        for slots in &mut self.rows[span.row..end_row] {
+            if slots.len() < end_col {
+                slots.resize(end_col, None);
+            }
+            for slot in &mut slots[span.col..end_col] {
+                if let Some(other) = slot.filter(|&other| other != cell) {
+                    replaced.get_or_insert(other);
+                }
+                *slot = Some(cell);
+            }
+        }
+        replaced
+    }
+}
+
+/// State of the HTML "forming a table" algorithm.
+///
+/// See <https://html.spec.whatwg.org/multipage/tables.html#forming-a-table>.
+/// Where cells overlap, the slot keeps the cell processed last. Documents are
+/// always treated as no-quirks, so `rowspan="0"` makes a cell grow to the
+/// end of its row group.
+///
+/// Every row and cell is checked against the [`ExtractOptions`] limits
+/// before it is placed, so an oversized table is never allocated.
+struct TableForming<'a> {
+    options: ExtractOptions,
+    /// Whether diagnostics are collected, which only pays off when the
+    /// caller returns them.
+    diagnose: bool,
+    diagnostics: Vec<Diagnostic>,
+    paths: NodePaths<'a>,
+    /// Set once a truncated table has no room for further rows.
+    full: bool,
+    /// Grid row after the last `<tr>` of the current row group.
+    group_end: usize,
+    /// The `<tr>` of each grid row, recorded in strict mode.
+    trs: Vec<Option<Node<'a>>>,
+    grid: SlotGrid,
+    cells: Vec<(Node<'a>, CellSpan)>,
+    x_width: usize,
+    y_height: usize,
+    y_current: usize,
+    /// Indices of the cells that grow downward to the end of the row group.
+    downward_growing: Vec<usize>,
+}
+
+impl<'a> TableForming<'a> {
+    fn new(
+        table: Node<'a>,
+        x_width: usize,
+        options: &ExtractOptions,
+        diagnose: bool,
+    ) -> Result<Self, Error> {
+        let mut forming = Self {
+            options: options.clone(),
+            diagnose,
+            diagnostics: vec![],
+            paths: NodePaths::default(),
+            full: false,
+            group_end: 0,
+            trs: vec![],
+            grid: SlotGrid { rows: vec![] },
+            cells: vec![],
+            x_width,
+            y_height: 0,
+            y_current: 0,
+            downward_growing: vec![],
+        };
+        if x_width > options.max_columns {
+            forming.exceeded(Limit::Columns, options.max_columns, x_width, |paths| {
+                Location {
+                    path: paths.path(table),
+                    ..Location::default()
+                }
+            })?;
+            forming.x_width = options.max_columns;
+        }
+        Ok(forming)
+    }
+
+    /// Processes the rows of one row group and returns the grid rows it
+    /// spans. Unless rowspans are clamped, these include rows added by cells
+    /// spanning past its last `<tr>`.
+    fn row_group(
+        &mut self,
+        t: &TableSupport<'a>,
+        rows: &[Node<'a>],
+    ) -> Result<Range<usize>, Error> {
+        let y_start = self.y_height;
+        self.group_end = y_start + rows.len();
+        for tr in rows {
+            if !self.row(t, *tr)? {
+                break;
+            }
+        }
+        let group = y_start..self.y_height;
+        while self.y_current < self.y_height {
+            self.grow_downward_growing_cells()?;
+            self.y_current += 1;
+        }
+        self.downward_growing.clear();
+        Ok(group)
+    }
+
+    /// Processes one `<tr>`. Returns `false` when a truncated table has no
+    /// room for the row.
+    fn row(&mut self, t: &TableSupport<'a>, tr: Node<'a>) -> Result<bool, Error> {
+        if self.full {
+            return Ok(false);
+        }
+        if self.y_height == self.y_current {
+            let row = self.y_current;
+            let location = |paths: &mut NodePaths<'a>| Location {
+                row: Some(row),
+                path: paths.path(tr),
+                ..Location::default()
             };
-            let (mut row_size, col_size) = element_utils::extract_rowspan_and_colspan(element);
-            let remaining_rows = tr_nodes.len() - row_index;
-            if row_size == 0 {
-                if rowspan_zero_seen {
-                    row_size = 1;
-                } else {
-                    row_size = remaining_rows;
-                    rowspan_zero_seen = true;
+            let (max_rows, max_cells) = (self.options.max_rows, self.options.max_cells);
+            let cells = self.x_width.saturating_mul(self.y_height + 1);
+            if self.y_height >= max_rows {
+                self.exceeded(Limit::Rows, max_rows, self.y_height + 1, location)?;
+                self.full = true;
+                return Ok(false);
+            }
+            if cells > max_cells {
+                self.exceeded(Limit::Cells, max_cells, cells, location)?;
+                self.full = true;
+                return Ok(false);
+            }
+            self.y_height += 1;
+        }
+        if self.grid.rows.len() < self.y_height {
+            self.grid.rows.resize(self.y_height, vec![]);
+        }
+        if self.options.strict {
+            self.trs.resize(self.y_height, None);
+            self.trs[self.y_current] = Some(tr);
+        }
+        self.grow_downward_growing_cells()?;
+        let mut x_current = 0;
+        for (td, element) in t.td_nodes(tr) {
+            x_current = self.grid.first_free(self.y_current, x_current);
+            self.check_spans(td, element, x_current);
+            let (mut rowspan, colspan) = element_utils::extract_rowspan_and_colspan(element);
+            let grows_downward = rowspan == 0;
+            let rows_left = self.group_end.saturating_sub(self.y_current).max(1);
+            if rowspan > rows_left {
+                if self.options.strict {
+                    self.rowspan_crosses_row_group(td, x_current, rowspan, rows_left);
+                }
+                if self.options.clamp_rowspans {
+                    rowspan = rows_left;
                 }
-            } else if row_size > remaining_rows {
-                row_size = remaining_rows;
-            }
-            while col_index < MAX_TABLE_COLUMNS && map.contains_key(&(row_index, col_index)) {
-                col_index += 1;
-            }
-            if col_index >= MAX_TABLE_COLUMNS {
-                return Err(Error::InvalidDocument(
-                    "table row exceeds the maximum number of columns",
-                ));
-            }
-            if col_size > MAX_TABLE_COLUMNS {
-                return Err(Error::InvalidDocument(
-                    "colspan exceeds the maximum number of columns",
-                ));
-            }
-            let Some(end_col) = col_index.checked_add(col_size) else {
-                return Err(Error::InvalidDocument(
-                    "column index overflowed while placing a cell",
-                ));
+            }
+            let Some((rowspan, colspan)) =
+                self.fit(td, element, x_current, rowspan.max(1), colspan)?
+            else {
+                break;
+            };
+            self.x_width = self.x_width.max(x_current + colspan);
+            self.y_height = self.y_height.max(self.y_current + rowspan);
+            let span = CellSpan {
+                row: self.y_current,
+                col: x_current,
+                rowspan,
+                colspan,
             };
-            if end_col > MAX_TABLE_COLUMNS {
-                return Err(Error::InvalidDocument(
-                    "cell placement exceeds the maximum number of columns",
-                ));
-            }
-            for k in 0..row_size {
-                for l in 0..col_size {
-                    let row = row_index + k;
-                    let col = col_index + l;
-                    map.insert((row, col), td_node);
+            let cell = self.cells.len();
+            self.cells.push((td, span));
+            if let Some(other) = self.grid.cover(span, cell) {
+                self.check_overlap(cell, other);
+            }
+            if grows_downward {
+                self.downward_growing.push(cell);
+            }
+            x_current += colspan;
+        }
+        self.y_current += 1;
+        Ok(true)
+    }
+
+    /// Checks a cell about to be placed at column `col` of the current row
+    /// against the limits. Returns its spans, shortened to fit when the
+    /// table is truncated, or `None` when there is no room left for it.
+    fn fit(
+        &mut self,
+        td: Node<'a>,
+        element: Element<'a>,
+        col: usize,
+        mut rowspan: usize,
+        mut colspan: usize,
+    ) -> Result<Option<(usize, usize)>, Error> {
+        let (max_columns, max_rows, max_cells, max_span_area) = (
+            self.options.max_columns,
+            self.options.max_rows,
+            self.options.max_cells,
+            self.options.max_span_area,
+        );
+        let row = self.y_current;
+        let location = |paths: &mut NodePaths<'a>, attribute: &str| Location {
+            row: Some(row),
+            col: Some(col),
+            path: paths.path(td),
+            attribute: element
+                .attribute_value(attribute)
+                .map(|value| (attribute.to_string(), value.to_string())),
+            ..Location::default()
+        };
+        let span_attribute = if element.attribute("rowspan").is_some() {
+            "rowspan"
+        } else {
+            "colspan"
+        };
+        if col + colspan > max_columns {
+            self.exceeded(Limit::Columns, max_columns, col + colspan, |paths| {
+                location(paths, "colspan")
+            })?;
+            if col >= max_columns {
+                return Ok(None);
+            }
+            colspan = max_columns - col;
+        }
+        let area = rowspan.saturating_mul(colspan);
+        if area > max_span_area {
+            self.exceeded(Limit::SpanArea, max_span_area, area, |paths| {
+                location(paths, span_attribute)
+            })?;
+            if max_span_area == 0 {
+                return Ok(None);
+            }
+            colspan = colspan.min(max_span_area);
+            rowspan = rowspan.min(max_span_area / colspan);
+        }
+        if self.y_current + rowspan > max_rows {
+            self.exceeded(Limit::Rows, max_rows, self.y_current + rowspan, |paths| {
+                location(paths, "rowspan")
+            })?;
+            rowspan = max_rows - self.y_current;
+        }
+        let width = self.x_width.max(col + colspan);
+        let cells = width.saturating_mul(self.y_height.max(self.y_current + rowspan));
+        if cells > max_cells {
+            self.exceeded(Limit::Cells, max_cells, cells, |paths| {
+                location(paths, span_attribute)
+            })?;
+            let max_height = max_cells / width;
+            if max_height < self.y_height {
+                return Ok(None);
+            }
+            rowspan = rowspan.min(max_height - self.y_current);
+        }
+        Ok(Some((rowspan, colspan)))
+    }
+
+    /// Records a diagnostic for every `rowspan` or `colspan` of the cell at
+    /// column `col` that is not a number or lies outside the range honored
+    /// by the HTML table model.
+    ///
+    /// Outside strict mode, a rowspan shortened to the end of its row group
+    /// is reported as clamped too.
+    fn check_spans(&mut self, td: Node<'a>, element: Element<'a>, col: usize) {
+        if !self.diagnose {
+            return;
+        }
+        let mut max_rowspan = element_utils::MAX_ROWSPAN;
+        if self.options.clamp_rowspans && !self.options.strict {
+            let rows_left = self.group_end.saturating_sub(self.y_current).max(1);
+            max_rowspan = max_rowspan.min(rows_left);
+        }
+        let spans = [
+            ("rowspan", 0, max_rowspan),
+            ("colspan", 1, element_utils::MAX_COLSPAN),
+        ];
+        for (attribute, min, max) in spans {
+            let Some(value) = element.attribute_value(attribute) else {
+                continue;
+            };
+            let kind = match element_utils::parse_span(value) {
+                None => DiagnosticKind::InvalidSpan { attribute },
+                Some(span) if !(min..=max).contains(&span) => DiagnosticKind::SpanClamped {
+                    attribute,
+                    clamped: span.clamp(min, max),
+                },
+                Some(_)
+                    if self.options.strict && !element_utils::is_non_negative_integer(value) =>
+                {
+                    DiagnosticKind::NonConformingSpan { attribute }
                 }
+                Some(_) => continue,
+            };
+            self.diagnostics.push(Diagnostic {
+                kind,
+                location: Location {
+                    row: Some(self.y_current),
+                    col: Some(col),
+                    path: self.paths.path(td),
+                    attribute: Some((attribute.to_string(), value.to_string())),
+                    ..Location::default()
+                },
+            });
+        }
+    }
+
+    /// Fails with [`Error::LimitExceeded`] unless the options truncate
+    /// tables that exceed a limit, in which case the truncation is recorded
+    /// as a diagnostic.
+    fn exceeded(
+        &mut self,
+        limit: Limit,
+        max: usize,
+        actual: usize,
+        location: impl FnOnce(&mut NodePaths<'a>) -> Location,
+    ) -> Result<(), Error> {
+        match self.options.on_limit {
+            LimitAction::Truncate => {
+                if self.diagnose {
+                    self.diagnostics.push(Diagnostic {
+                        kind: DiagnosticKind::Truncated { limit, max, actual },
+                        location: location(&mut self.paths),
+                    });
+                }
+                Ok(())
+            }
+            LimitAction::Error | LimitAction::Skip => Err(limit_exceeded(
+                limit,
+                max,
+                actual,
+                location(&mut self.paths),
+            )),
+        }
+    }
+
+    /// Extends every downward-growing cell over the current row.
+    ///
+    /// Cells only grow into rows already checked against the row and cell
+    /// limits, so only their span area is checked here. A cell growing past
+    /// it fails the table or, when truncating, stops growing.
+    fn grow_downward_growing_cells(&mut self) -> Result<(), Error> {
+        let mut i = 0;
+        while i < self.downward_growing.len() {
+            let cell = self.downward_growing[i];
+            let (td, span) = self.cells[cell];
+            let rowspan = self.y_current + 1 - span.row;
+            let area = rowspan.saturating_mul(span.colspan);
+            let max = self.options.max_span_area;
+            if area > max {
+                self.exceeded(Limit::SpanArea, max, area, |paths| Location {
+                    row: Some(span.row),
+                    col: Some(span.col),
+                    path: paths.path(td),
+                    attribute: td.element().and_then(|element| {
+                        let value = element.attribute_value("rowspan")?;
+                        Some(("rowspan".to_string(), value.to_string()))
+                    }),
+                    ..Location::default()
+                })?;
+                self.downward_growing.remove(i);
+                continue;
+            }
+            self.cells[cell].1.rowspan = rowspan;
+            let row_span = CellSpan {
+                row: self.y_current,
+                rowspan: 1,
+                ..span
+            };
+            if let Some(other) = self.grid.cover(row_span, cell) {
+                self.check_overlap(cell, other);
+            }
+            i += 1;
+        }
+        Ok(())
+    }
+
+    /// Records in strict mode that `cell` was placed over `other`.
+    fn check_overlap(&mut self, cell: usize, other: usize) {
+        if !self.options.strict {
+            return;
+        }
+        let (td, span) = self.cells[cell];
+        self.diagnostics.push(Diagnostic {
+            kind: DiagnosticKind::OverlappingCells {
+                other: self.paths.path(self.cells[other].0),
+            },
+            location: Location {
+                row: Some(span.row),
+                col: Some(span.col),
+                path: self.paths.path(td),
+                ..Location::default()
+            },
+        });
+    }
+
+    /// Records in strict mode that the cell at column `col` of the current
+    /// row spans `rowspan` rows while its row group ends `rows_left` rows
+    /// from it.
+    fn rowspan_crosses_row_group(
+        &mut self,
+        td: Node<'a>,
+        col: usize,
+        rowspan: usize,
+        rows_left: usize,
+    ) {
+        self.diagnostics.push(Diagnostic {
+            kind: DiagnosticKind::RowspanCrossesRowGroup {
+                rowspan,
+                rows_in_group: rows_left,
+            },
+            location: Location {
+                row: Some(self.y_current),
+                col: Some(col),
+                path: self.paths.path(td),
+                attribute: td.element().and_then(|element| {
+                    let value = element.attribute_value("rowspan")?;
+                    Some(("rowspan".to_string(), value.to_string()))
+                }),
+                ..Location::default()
+            },
+        });
+    }
+
+    /// Records the rows in which some slots are not covered by any cell.
+    fn check_ragged_rows(&mut self, table: Node<'a>) {
+        for (row, slots) in self.grid.rows.iter().take(self.y_height).enumerate() {
+            let covered = slots.iter().filter(|slot| slot.is_some()).count();
+            if covered == self.x_width {
+                continue;
             }
+            let node = self.trs.get(row).copied().flatten().unwrap_or(table);
+            self.diagnostics.push(Diagnostic {
+                kind: DiagnosticKind::RaggedRow {
+                    covered,
+                    width: self.x_width,
+                },
+                location: Location {
+                    row: Some(row),
+                    path: self.paths.path(node),
+                    ..Location::default()
+                },
+            });
         }
     }
-    let rows = map
-        .keys()
-        .map(|(i, _)| i)
-        .max()
-        .map(|&i| i + 1)
-        .unwrap_or(0);
-    let cols = map
-        .keys()
-        .map(|(_, j)| j)
-        .max()
-        .map(|&j| j + 1)
-        .unwrap_or(0);
-    let mut table = Table::new((rows, cols));
-    for ((i, j), item) in map {
-        table.set(i, j, item);
+}
+
+fn limit_exceeded(limit: Limit, max: usize, actual: usize, location: Location) -> Error {
+    Error::LimitExceeded {
+        limit,
+        max,
+        actual,
+        location: Box::new(location),
+    }
+}
+
+pub(crate) fn node_to_table_with<'a>(
+    node: Node<'a>,
+    options: &ExtractOptions,
+) -> Result<Table<Node<'a>>, Error> {
+    let (table, diagnostics) = form(node, options, options.strict)?;
+    if options.strict {
+        let findings: Vec<_> = diagnostics
+            .into_iter()
+            .filter(|diagnostic| !matches!(diagnostic.kind, DiagnosticKind::Truncated { .. }))
+            .collect();
+        if !findings.is_empty() {
+            return Err(Error::Validation { findings });
+        }
     }
     Ok(table)
 }
+
+/// Forms the table of `node`, along with the diagnostics collected on the
+/// way when `diagnose` is set.
+fn form<'a>(
+    node: Node<'a>,
+    options: &ExtractOptions,
+    diagnose: bool,
+) -> Result<(Table<Node<'a>>, Vec<Diagnostic>), Error> {
+    let t = TableSupport(node);
+    let mut forming = TableForming::new(t.0, t.leading_column_count(), options, diagnose)?;
+    let mut groups = vec![];
+    for (section, rows) in t.row_groups() {
+        if !rows.is_empty() {
+            groups.push((section, forming.row_group(&t, &rows)?));
+        }
+    }
+    if options.strict {
+        forming.check_ragged_rows(t.0);
+    }
+    let mut table = Table::new((forming.y_height, forming.x_width));
+    for (i, slots) in forming.grid.rows.iter().take(forming.y_height).enumerate() {
+        for (j, slot) in slots.iter().enumerate() {
+            let Some(&(item, span)) = slot.map(|cell| &forming.cells[cell]) else {
+                continue;
+            };
+            let is_header = item
+                .element()
+                .is_some_and(|element| element_utils::is_html_element(element, "th"));
+            table.set_slot(i, j, item, span);
+            table.set_header(i, j, is_header);
+        }
+    }
+    for (section, rows) in groups {
+        if !rows.is_empty() {
+            table.push_row_group(section, rows);
+        }
+    }
+    let info = t.info();
+    let mut columns = info.column_infos();
+    columns.truncate(forming.x_width);
+    table.set_columns(columns);
+    table.set_info(info);
+    Ok((table, forming.diagnostics))
+}
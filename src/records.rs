@@ -0,0 +1,378 @@
+use sxd_xpath::nodeset::Node;
+
+use crate::table::{Section, Table};
+#[cfg(feature = "serde")]
+use crate::Error;
+
+/// Separator placed between the levels of a multi-row header.
+pub const HEADER_LEVEL_SEPARATOR: &str = " / ";
+
+/// How the header rows of a table are determined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HeaderRows {
+    /// Treat the leading rows as headers while they come from `<thead>` or
+    /// consist only of `<th>` cells.
+    #[default]
+    Detect,
+    /// Treat exactly this many leading rows as headers.
+    Count(usize),
+}
+
+/// Rows of a table keyed by the column names taken from its header rows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Records {
+    header_rows: usize,
+    columns: Vec<String>,
+    rows: Vec<Vec<Option<String>>>,
+}
+
+impl Records {
+    /// Returns the column names, one per grid column.
+    ///
+    /// Multi-row headers are joined with [`HEADER_LEVEL_SEPARATOR`], so a
+    /// `2023` cell spanning `Q1` and `Q2` yields `"2023 / Q1"` and
+    /// `"2023 / Q2"`.
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// Returns the number of leading grid rows used as the header.
+    pub fn header_rows(&self) -> usize {
+        self.header_rows
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<Record<'_>> {
+        self.rows.get(index).map(|values| Record {
+            columns: &self.columns,
+            values,
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Record<'_>> {
+        self.rows.iter().map(|values| Record {
+            columns: &self.columns,
+            values,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Records {
+    /// Deserializes every record into `T`, matching column names to fields.
+    ///
+    /// Field names follow serde's `rename`/`alias` attributes. Empty grid
+    /// slots are passed as empty strings, so `Option<_>` fields become
+    /// `None`. The first failing row is reported as [`Error::Deserialize`].
+    pub fn deserialize<T>(&self) -> Result<Vec<T>, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        self.deserialize_each().collect()
+    }
+
+    /// Deserializes the records one by one, yielding a result per row.
+    ///
+    /// Errors cite the grid row (header rows included) and, when the failure
+    /// is tied to a field, the grid column.
+    pub fn deserialize_each<T>(&self) -> impl Iterator<Item = Result<T, Error>> + '_
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let headers = csv::StringRecord::from(self.columns.clone());
+        self.rows.iter().enumerate().map(move |(index, values)| {
+            let record: csv::StringRecord = values
+                .iter()
+                .map(|value| value.as_deref().unwrap_or(""))
+                .collect();
+            record
+                .deserialize(Some(&headers))
+                .map_err(|err| deserialize_error(self.header_rows + index, err))
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+fn deserialize_error(row: usize, err: csv::Error) -> Error {
+    match err.kind() {
+        csv::ErrorKind::Deserialize { err, .. } => Error::Deserialize {
+            row,
+            column: err.field().map(|field| field as usize),
+            message: err.kind().to_string(),
+        },
+        _ => Error::Deserialize {
+            row,
+            column: None,
+            message: err.to_string(),
+        },
+    }
+}
+
+/// A single body row of [`Records`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Record<'r> {
+    columns: &'r [String],
+    values: &'r [Option<String>],
+}
+
+impl<'r> Record<'r> {
+    /// Returns the value of the first column named `column`.
+    ///
+    /// Returns `None` both when no column has that name and when the grid
+    /// slot under it is empty.
+    pub fn get(&self, column: &str) -> Option<&'r str> {
+        let index = self.columns.iter().position(|name| name == column)?;
+        self.values[index].as_deref()
+    }
+
+    pub fn values(&self) -> &'r [Option<String>] {
+        self.values
+    }
+
+    /// Iterates over `(column name, value)` pairs in column order.
+    pub fn iter(&self) -> impl Iterator<Item = (&'r str, Option<&'r str>)> {
+        self.columns
+            .iter()
+            .zip(self.values)
+            .map(|(name, value)| (name.as_str(), value.as_deref()))
+    }
+}
+
+impl<T> Table<T> {
+    /// Returns the number of leading rows that form the table header.
+    pub fn header_row_count(&self, header: HeaderRows) -> usize {
+        let (rows, cols) = self.size();
+        match header {
+            HeaderRows::Count(count) => count.min(rows),
+            HeaderRows::Detect => (0..rows)
+                .take_while(|&row| {
+                    let in_head = self
+                        .row_group(row)
+                        .is_some_and(|group| group.section == Section::Head);
+                    let mut cells = (0..cols).filter_map(|col| self.cell(row, col)).peekable();
+                    in_head || (cells.peek().is_some() && cells.all(|cell| cell.header))
+                })
+                .count(),
+        }
+    }
+}
+
+impl<'a> Table<Node<'a>> {
+    /// Splits the table into column names and body records.
+    ///
+    /// Cell text is taken from [`Node::string_value`] and trimmed. Header
+    /// cells spanning several header rows contribute their text only once
+    /// to each column name.
+    pub fn to_records(&self, header: HeaderRows) -> Records {
+        let header_rows = self.header_row_count(header);
+        let rows = self.rows();
+        let columns = column_names(self, header_rows, |node| node.string_value());
+        let rows = rows[header_rows..]
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| cell.map(|node| node.string_value().trim().to_string()))
+                    .collect()
+            })
+            .collect();
+        Records {
+            header_rows,
+            columns,
+            rows,
+        }
+    }
+
+    /// Deserializes the body rows into `T` using the header rows as field
+    /// names. See [`Records::deserialize`].
+    #[cfg(feature = "serde")]
+    pub fn deserialize<T>(&self, header: HeaderRows) -> Result<Vec<T>, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        self.to_records(header).deserialize()
+    }
+}
+
+/// Builds one name per column from the first `header_rows` rows, joining
+/// the levels of multi-row headers with [`HEADER_LEVEL_SEPARATOR`].
+///
+/// A cell spanning several header rows contributes its text only once, and
+/// empty header texts are skipped.
+pub(crate) fn column_names<T>(
+    table: &Table<T>,
+    header_rows: usize,
+    text: impl Fn(&T) -> String,
+) -> Vec<String> {
+    let (_, cols) = table.size();
+    (0..cols)
+        .map(|col| {
+            let mut levels: Vec<String> = vec![];
+            let mut previous = None;
+            for row in 0..header_rows {
+                let Some(cell) = table.cell(row, col) else {
+                    continue;
+                };
+                if previous == Some(cell.span) {
+                    continue;
+                }
+                previous = Some(cell.span);
+                let text = text(cell.value);
+                let text = text.trim();
+                if !text.is_empty() {
+                    levels.push(text.to_string());
+                }
+            }
+            levels.join(HEADER_LEVEL_SEPARATOR)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract_table_nodes_to_table;
+
+    #[test]
+    fn detects_thead_rows_and_joins_multi_row_headers() {
+        let html = r#"
+        <table>
+            <thead>
+                <tr><td rowspan="2">Region</td><td colspan="2">2023</td></tr>
+                <tr><td>Q1</td><td>Q2</td></tr>
+            </thead>
+            <tbody>
+                <tr><td>East</td><td>10</td><td>20</td></tr>
+                <tr><td>West</td><td>30</td><td>40</td></tr>
+            </tbody>
+        </table>
+        "#;
+        let package = sxd_html::parse_html(html);
+        let document = package.as_document();
+        let tables = extract_table_nodes_to_table(document.root()).unwrap();
+        let records = tables[0].to_records(HeaderRows::Detect);
+
+        assert_eq!(records.header_rows(), 2);
+        assert_eq!(records.columns(), ["Region", "2023 / Q1", "2023 / Q2"]);
+        assert_eq!(records.len(), 2);
+        let west = records.get(1).unwrap();
+        assert_eq!(west.get("Region"), Some("West"));
+        assert_eq!(west.get("2023 / Q2"), Some("40"));
+        assert_eq!(west.get("missing"), None);
+    }
+
+    #[test]
+    fn detects_rows_made_of_th_cells() {
+        let html = r#"
+        <table>
+            <tr><th>Name</th><th>Age</th></tr>
+            <tr><th>Alice</th><td>30</td></tr>
+        </table>
+        "#;
+        let package = sxd_html::parse_html(html);
+        let document = package.as_document();
+        let tables = extract_table_nodes_to_table(document.root()).unwrap();
+        let records = tables[0].to_records(HeaderRows::Detect);
+
+        assert_eq!(records.columns(), ["Name", "Age"]);
+        let pairs: Vec<_> = records.get(0).unwrap().iter().collect();
+        assert_eq!(pairs, [("Name", Some("Alice")), ("Age", Some("30"))]);
+    }
+
+    #[test]
+    fn explicit_count_overrides_detection() {
+        let html = r#"
+        <table>
+            <tr><td>id</td><td>value</td></tr>
+            <tr><td>1</td><td>a</td></tr>
+        </table>
+        "#;
+        let package = sxd_html::parse_html(html);
+        let document = package.as_document();
+        let tables = extract_table_nodes_to_table(document.root()).unwrap();
+
+        assert_eq!(tables[0].header_row_count(HeaderRows::Detect), 0);
+        let records = tables[0].to_records(HeaderRows::Count(1));
+        assert_eq!(records.columns(), ["id", "value"]);
+        assert_eq!(records.get(0).unwrap().get("value"), Some("a"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserializes_records_by_column_name() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Row {
+            #[serde(rename = "Age")]
+            age: u32,
+            #[serde(alias = "Name")]
+            name: String,
+            note: Option<String>,
+        }
+
+        let html = r#"
+        <table>
+            <tr><th>Age</th><th>Name</th><th>note</th></tr>
+            <tr><td>30</td><td>Alice</td><td></td></tr>
+            <tr><td>25</td><td>Bob</td><td>new</td></tr>
+        </table>
+        "#;
+        let package = sxd_html::parse_html(html);
+        let document = package.as_document();
+        let tables = extract_table_nodes_to_table(document.root()).unwrap();
+        let rows: Vec<Row> = tables[0].deserialize(HeaderRows::Detect).unwrap();
+
+        assert_eq!(
+            rows,
+            [
+                Row {
+                    age: 30,
+                    name: "Alice".to_string(),
+                    note: None,
+                },
+                Row {
+                    age: 25,
+                    name: "Bob".to_string(),
+                    note: Some("new".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_error_cites_grid_position() {
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        struct Row {
+            name: String,
+            age: u32,
+        }
+
+        let html = r#"
+        <table>
+            <tr><th>name</th><th>age</th></tr>
+            <tr><td>Alice</td><td>30</td></tr>
+            <tr><td>Bob</td><td>unknown</td></tr>
+        </table>
+        "#;
+        let package = sxd_html::parse_html(html);
+        let document = package.as_document();
+        let tables = extract_table_nodes_to_table(document.root()).unwrap();
+        let records = tables[0].to_records(HeaderRows::Detect);
+        let results: Vec<Result<Row, Error>> = records.deserialize_each().collect();
+
+        assert!(results[0].is_ok());
+        match &results[1] {
+            Err(Error::Deserialize { row, column, .. }) => {
+                assert_eq!((*row, *column), (2, Some(1)));
+            }
+            other => panic!("expected Deserialize error, got {other:?}"),
+        }
+    }
+}
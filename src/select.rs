@@ -0,0 +1,434 @@
+use std::fmt;
+
+use sxd_xpath::{nodeset::Node, Value};
+
+use crate::{
+    element_utils,
+    extract::ExtractOptions,
+    node_utils::{
+        all_table_nodes, caption_text, child_table_nodes, evaluate_xpath_node, form_table,
+    },
+    records::HeaderRows,
+    table::Table,
+    Error,
+};
+
+/// A criterion for picking tables out of a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableSelector {
+    /// Tables selected by an XPath expression evaluated from the given node.
+    /// Selected nodes that are not `<table>` elements are ignored.
+    XPath(String),
+    /// Tables matched by a CSS-like selector such as `table#prices`,
+    /// `.wikitable` or `div.content > table[summary]`.
+    ///
+    /// Type, `#id`, `.class`, `[attr]` and `[attr=value]` selectors are
+    /// supported, combined with the descendant (space) and child (`>`)
+    /// combinators. The last compound selector must match a `<table>`.
+    Css(String),
+    /// Tables whose whitespace-normalized `<caption>` text contains the
+    /// given string.
+    Caption(String),
+    /// Tables with a header cell whose trimmed text equals the given string.
+    /// The header rows are detected as with [`HeaderRows::Detect`], falling
+    /// back to the first row when none are detected.
+    Header(String),
+    /// The table at this position among the tables below the given node
+    /// that are not nested in another of them. From the document root, this
+    /// is the table at the same index of [`extract_table_nodes_to_table`].
+    ///
+    /// [`extract_table_nodes_to_table`]: crate::extract_table_nodes_to_table
+    Index(usize),
+}
+
+impl fmt::Display for TableSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::XPath(expr) => write!(f, "XPath {expr:?}"),
+            Self::Css(selector) => write!(f, "CSS selector {selector:?}"),
+            Self::Caption(text) => write!(f, "caption containing {text:?}"),
+            Self::Header(text) => write!(f, "header {text:?}"),
+            Self::Index(index) => write!(f, "table index {index}"),
+        }
+    }
+}
+
+/// Returns every table matching `selector`, in document order.
+///
+/// Fails with [`Error::TableNotFound`], describing the selector, when nothing
+/// matches. Apart from [`TableSelector::Index`], nested tables are candidates
+/// too.
+pub fn select_tables<'a>(
+    node: impl Into<Node<'a>>,
+    selector: &TableSelector,
+) -> Result<Vec<Table<Node<'a>>>, Error> {
+    select_tables_with(node, selector, &ExtractOptions::default())
+}
+
+/// Returns every table matching `selector`, formed within the limits of
+/// `options`.
+///
+/// The table limit of `options` does not apply. A candidate exceeding any
+/// other limit fails the selection, is truncated or, with
+/// [`LimitAction::Skip`], is left out as if it did not match.
+///
+/// [`LimitAction::Skip`]: crate::extract::LimitAction::Skip
+pub fn select_tables_with<'a>(
+    node: impl Into<Node<'a>>,
+    selector: &TableSelector,
+    options: &ExtractOptions,
+) -> Result<Vec<Table<Node<'a>>>, Error> {
+    select(node.into(), selector, options, false)
+}
+
+/// Returns the first table matching `selector` in document order.
+///
+/// ```
+/// use sxd_html_table::select::{select_table, TableSelector};
+///
+/// let package = sxd_html::parse_html(
+///     r#"<table><tr><td>nav</td></tr></table>
+///        <table id="prices"><tr><th>Item</th><th>Price</th></tr></table>"#,
+/// );
+/// let root = package.as_document().root();
+/// let by_id = select_table(root, &TableSelector::Css("table#prices".into())).unwrap();
+/// let by_header = select_table(root, &TableSelector::Header("Price".into())).unwrap();
+/// assert_eq!(by_id.to_string_table().to_csv().unwrap(), "Item,Price\n");
+/// assert_eq!(by_header.to_string_table().to_csv().unwrap(), "Item,Price\n");
+/// ```
+pub fn select_table<'a>(
+    node: impl Into<Node<'a>>,
+    selector: &TableSelector,
+) -> Result<Table<Node<'a>>, Error> {
+    select_table_with(node, selector, &ExtractOptions::default())
+}
+
+/// Returns the first table matching `selector`, formed within the limits of
+/// `options`. Candidates after it are not formed.
+pub fn select_table_with<'a>(
+    node: impl Into<Node<'a>>,
+    selector: &TableSelector,
+    options: &ExtractOptions,
+) -> Result<Table<Node<'a>>, Error> {
+    let mut tables = select(node.into(), selector, options, true)?;
+    Ok(tables.remove(0))
+}
+
+/// Forms the tables matching `selector`, stopping after the first one when
+/// `first_only` is set.
+fn select<'a>(
+    node: Node<'a>,
+    selector: &TableSelector,
+    options: &ExtractOptions,
+    first_only: bool,
+) -> Result<Vec<Table<Node<'a>>>, Error> {
+    let candidates = match selector {
+        TableSelector::XPath(expr) => xpath_tables(node, expr)?,
+        TableSelector::Css(selector) => xpath_tables(node, &css_to_xpath(selector)?)?,
+        TableSelector::Caption(text) => all_table_nodes(node)
+            .into_iter()
+            .filter(|table| {
+                caption_text(*table).is_some_and(|caption| caption.contains(text.as_str()))
+            })
+            .collect(),
+        TableSelector::Header(_) => all_table_nodes(node),
+        TableSelector::Index(index) => child_table_nodes(node)
+            .into_iter()
+            .skip(*index)
+            .take(1)
+            .collect(),
+    };
+    let mut tables = vec![];
+    for candidate in candidates {
+        let Some(table) = form_table(candidate, options)? else {
+            continue;
+        };
+        if let TableSelector::Header(text) = selector {
+            if !has_header(&table, text) {
+                continue;
+            }
+        }
+        tables.push(table);
+        if first_only {
+            break;
+        }
+    }
+    non_empty(tables, selector)
+}
+
+fn non_empty<T>(tables: Vec<T>, selector: &TableSelector) -> Result<Vec<T>, Error> {
+    if tables.is_empty() {
+        return Err(Error::TableNotFound {
+            criterion: Some(selector.to_string()),
+        });
+    }
+    Ok(tables)
+}
+
+fn xpath_tables<'a>(node: Node<'a>, expr: &str) -> Result<Vec<Node<'a>>, Error> {
+    let Value::Nodeset(nodes) = evaluate_xpath_node(node, expr)? else {
+        return Ok(vec![]);
+    };
+    Ok(nodes
+        .document_order()
+        .into_iter()
+        .filter(|node| {
+            node.element()
+                .is_some_and(|element| element_utils::is_html_element(element, "table"))
+        })
+        .collect())
+}
+
+fn has_header(table: &Table<Node<'_>>, text: &str) -> bool {
+    let header_rows = match table.header_row_count(HeaderRows::Detect) {
+        0 => 1,
+        count => count,
+    };
+    table
+        .rows()
+        .iter()
+        .take(header_rows)
+        .flatten()
+        .flatten()
+        .any(|node| node.string_value().trim() == text)
+}
+
+/// Translates the supported subset of CSS selectors into an XPath
+/// expression selecting `<table>` elements.
+fn css_to_xpath(selector: &str) -> Result<String, Error> {
+    let invalid = |reason: &str| Error::InvalidSelector(format!("{selector:?}: {reason}"));
+    let mut xpath = String::from("//");
+    let mut chars = selector.trim().chars().peekable();
+    let mut compound = String::new();
+    let mut element = String::from("*");
+    let mut predicates = String::new();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '#' | '.' => {
+                let name = take_identifier(&mut chars);
+                if name.is_empty() {
+                    return Err(invalid("expected a name after '#' or '.'"));
+                }
+                if ch == '#' {
+                    predicates.push_str(&format!("[@id={}]", xpath_literal(&name)?));
+                } else {
+                    predicates.push_str(&format!(
+                        "[contains(concat(' ', normalize-space(@class), ' '), {})]",
+                        xpath_literal(&format!(" {name} "))?
+                    ));
+                }
+                compound.push(ch);
+            }
+            '[' => {
+                let mut attribute = String::new();
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some(c) => attribute.push(c),
+                        None => return Err(invalid("unterminated attribute selector")),
+                    }
+                }
+                let (name, value) = match attribute.split_once('=') {
+                    Some((name, value)) => (name.trim(), Some(value.trim())),
+                    None => (attribute.trim(), None),
+                };
+                if name.is_empty() || !name.chars().all(is_identifier_char) {
+                    return Err(invalid("unsupported attribute selector"));
+                }
+                match value {
+                    None => predicates.push_str(&format!("[@{name}]")),
+                    Some(value) => {
+                        let value = value.trim_matches(|c| c == '"' || c == '\'');
+                        predicates.push_str(&format!("[@{name}={}]", xpath_literal(value)?));
+                    }
+                }
+                compound.push(ch);
+            }
+            ' ' | '>' => {
+                let mut child = ch == '>';
+                while let Some(&next) = chars.peek() {
+                    match next {
+                        ' ' => {}
+                        '>' if !child => child = true,
+                        _ => break,
+                    }
+                    chars.next();
+                }
+                if compound.is_empty() {
+                    return Err(invalid("combinator without a preceding selector"));
+                }
+                xpath.push_str(&compound_xpath(&element, &predicates));
+                xpath.push_str(if child { "/" } else { "//" });
+                compound.clear();
+                element = String::from("*");
+                predicates.clear();
+            }
+            '*' if compound.is_empty() => compound.push(ch),
+            c if is_identifier_char(c) && compound.is_empty() => {
+                element = format!("{c}{}", take_identifier(&mut chars)).to_ascii_lowercase();
+                compound.push_str(&element);
+            }
+            _ => return Err(invalid(&format!("unsupported character {ch:?}"))),
+        }
+    }
+    if compound.is_empty() {
+        return Err(invalid("empty selector"));
+    }
+    if element != "*" && element != "table" {
+        return Err(invalid("the selector must match <table> elements"));
+    }
+    xpath.push_str(&compound_xpath("table", &predicates));
+    Ok(xpath)
+}
+
+fn compound_xpath(element: &str, predicates: &str) -> String {
+    if element == "*" {
+        format!("*{predicates}")
+    } else {
+        format!("*[local-name() = '{element}']{predicates}")
+    }
+}
+
+fn take_identifier(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+    std::iter::from_fn(|| chars.next_if(|&c| is_identifier_char(c))).collect()
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_'
+}
+
+fn xpath_literal(value: &str) -> Result<String, Error> {
+    if !value.contains('\'') {
+        Ok(format!("'{value}'"))
+    } else if !value.contains('"') {
+        Ok(format!("\"{value}\""))
+    } else {
+        Err(Error::InvalidSelector(format!(
+            "{value:?}: values containing both quote characters are not supported"
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HTML: &str = r#"
+    <html>
+        <body>
+            <table class="nav"><tr><td>menu</td></tr></table>
+            <div class="content">
+                <table id="prices" class="wikitable sortable">
+                    <caption>
+                        Fruit   prices
+                    </caption>
+                    <thead><tr><th>Item</th><th>Price</th></tr></thead>
+                    <tr><td>apple</td><td>1</td></tr>
+                </table>
+            </div>
+            <table summary="stock"><tr><td>Item</td><td>Stock</td></tr></table>
+        </body>
+    </html>
+    "#;
+
+    fn first_cell(selector: TableSelector) -> Result<String, Error> {
+        let package = sxd_html::parse_html(HTML);
+        let document = package.as_document();
+        let table = select_table(document.root(), &selector)?;
+        Ok(table.to_string_table().rows()[0][0].unwrap().clone())
+    }
+
+    #[test]
+    fn selects_by_each_criterion() {
+        let css = |selector: &str| first_cell(TableSelector::Css(selector.to_string())).unwrap();
+        assert_eq!(css("table#prices"), "Item");
+        assert_eq!(css(".wikitable"), "Item");
+        assert_eq!(css("div.content > table.sortable"), "Item");
+        assert_eq!(css("body table[summary=stock]"), "Item");
+        assert_eq!(css("table[summary]"), "Item");
+        assert_eq!(css("table.nav"), "menu");
+
+        let xpath = TableSelector::XPath("//table[@summary]".to_string());
+        assert_eq!(first_cell(xpath).unwrap(), "Item");
+        let caption = TableSelector::Caption("Fruit prices".to_string());
+        assert_eq!(first_cell(caption).unwrap(), "Item");
+        let header = TableSelector::Header("Price".to_string());
+        assert_eq!(first_cell(header).unwrap(), "Item");
+        let header = TableSelector::Header("Stock".to_string());
+        assert_eq!(first_cell(header).unwrap(), "Item");
+        assert_eq!(first_cell(TableSelector::Index(0)).unwrap(), "menu");
+    }
+
+    #[test]
+    fn index_counts_the_tables_below_the_given_node() {
+        let package = sxd_html::parse_html(HTML);
+        let root = package.as_document().root();
+        let Value::Nodeset(content) = evaluate_xpath_node(root, "//div[@class='content']").unwrap()
+        else {
+            panic!("expected a node-set");
+        };
+        let content = content.document_order()[0];
+        let table = select_table(content, &TableSelector::Index(0)).unwrap();
+        assert_eq!(table.info().id.as_deref(), Some("prices"));
+        let result = select_table(content, &TableSelector::Index(1));
+        assert!(matches!(
+            result,
+            Err(Error::TableNotFound { criterion: Some(_) })
+        ));
+    }
+
+    #[test]
+    fn reports_the_failed_criterion() {
+        let err = first_cell(TableSelector::Header("Weight".to_string())).unwrap_err();
+        assert_eq!(err.to_string(), "no table matched header \"Weight\"");
+        let err = first_cell(TableSelector::Index(3)).unwrap_err();
+        assert!(matches!(err, Error::TableNotFound { criterion: Some(_) }));
+        let err = first_cell(TableSelector::Css("table#missing".to_string())).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no table matched CSS selector \"table#missing\""
+        );
+        let err = first_cell(TableSelector::Css("div.content".to_string())).unwrap_err();
+        assert!(matches!(err, Error::InvalidSelector(_)));
+        let err = first_cell(TableSelector::Css("table:first-child".to_string())).unwrap_err();
+        assert!(matches!(err, Error::InvalidSelector(_)));
+        let err = first_cell(TableSelector::Css("table[summary".to_string())).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid selector: \"table[summary\": unterminated attribute selector"
+        );
+    }
+
+    #[test]
+    fn selection_applies_the_extract_options() {
+        let package = sxd_html::parse_html(HTML);
+        let root = package.as_document().root();
+        let selector = TableSelector::Css("table#prices".to_string());
+        let options = ExtractOptions::new().max_columns(1);
+        let result = select_table_with(root, &selector, &options);
+        assert!(matches!(result, Err(Error::LimitExceeded { .. })));
+
+        let options = options.on_limit(crate::extract::LimitAction::Truncate);
+        let table = select_table_with(root, &selector, &options).unwrap();
+        assert_eq!(table.to_string_table().to_csv().unwrap(), "Item\napple\n");
+    }
+
+    #[test]
+    fn skipped_tables_do_not_fail_the_selection() {
+        let package = sxd_html::parse_html(
+            r#"<table><tr><td colspan="5">wide</td></tr></table>
+               <table><tr><th>Price</th></tr><tr><td>1</td></tr></table>"#,
+        );
+        let root = package.as_document().root();
+        let header = TableSelector::Header("Price".to_string());
+        let options = ExtractOptions::new().max_columns(2);
+        let result = select_table_with(root, &header, &options);
+        assert!(matches!(result, Err(Error::LimitExceeded { .. })));
+
+        let options = options.on_limit(crate::extract::LimitAction::Skip);
+        let table = select_table_with(root, &header, &options).unwrap();
+        assert_eq!(table.to_string_table().to_csv().unwrap(), "Price\n1\n");
+        let all = TableSelector::XPath("//table".to_string());
+        assert_eq!(select_tables_with(root, &all, &options).unwrap().len(), 1);
+    }
+}
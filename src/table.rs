@@ -1,18 +1,350 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, num::NonZeroUsize, ops::Range};
 
-use crate::Error;
+use crate::{Error, HeaderRows};
 
-fn sanitize_formula_injection(s: &str) -> Cow<'_, str> {
-    if s.starts_with(['=', '+', '-', '@', '\t', '\r']) {
-        Cow::Owned(format!("\t{s}"))
-    } else {
-        Cow::Borrowed(s)
+fn is_formula_like(s: &str) -> bool {
+    s.starts_with(['=', '+', '-', '@', '\t', '\r'])
+}
+
+/// How [`Table::write_csv_with`] treats cells that a spreadsheet could
+/// interpret as a formula: those starting with `=`, `+`, `-`, `@`, a tab or a
+/// carriage return.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InjectionPolicy {
+    /// Prefix such cells with a tab character.
+    #[default]
+    TabPrefix,
+    /// Prefix such cells with a single quote.
+    QuotePrefix,
+    /// Fail with [`Error::CsvInjection`] on the first such cell.
+    Reject,
+    /// Write cells unchanged, for pipelines that never open the output in a
+    /// spreadsheet.
+    Off,
+}
+
+/// Quoting rule for CSV fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QuoteStyle {
+    /// Quote fields only when they contain a delimiter, quote or line break.
+    #[default]
+    Necessary,
+    Always,
+    /// Quote every field that does not parse as a number.
+    NonNumeric,
+    /// Never quote fields, even if that produces invalid CSV.
+    Never,
+}
+
+/// Line terminator written after each CSV record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineTerminator {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+/// CSV dialect and sanitizing options for [`Table::write_csv_with`].
+///
+/// The default matches [`Table::write_csv`]: comma-separated, minimal
+/// quoting, `\n` line endings, no byte order mark, header rows included and
+/// [`InjectionPolicy::TabPrefix`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvOptions {
+    delimiter: u8,
+    quote_style: QuoteStyle,
+    terminator: LineTerminator,
+    bom: bool,
+    header: bool,
+    injection: InjectionPolicy,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            quote_style: QuoteStyle::default(),
+            terminator: LineTerminator::default(),
+            bom: false,
+            header: true,
+            injection: InjectionPolicy::default(),
+        }
+    }
+}
+
+impl CsvOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the field delimiter, e.g. `b'\t'` for TSV or `b';'`.
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn quote_style(mut self, quote_style: QuoteStyle) -> Self {
+        self.quote_style = quote_style;
+        self
+    }
+
+    pub fn terminator(mut self, terminator: LineTerminator) -> Self {
+        self.terminator = terminator;
+        self
+    }
+
+    /// Starts the output with a UTF-8 byte order mark so Excel detects the
+    /// encoding.
+    pub fn bom(mut self, bom: bool) -> Self {
+        self.bom = bom;
+        self
+    }
+
+    /// Includes or omits the header rows, as found by
+    /// [`Table::header_row_count`] with [`HeaderRows::Detect`].
+    pub fn header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    pub fn injection(mut self, injection: InjectionPolicy) -> Self {
+        self.injection = injection;
+        self
+    }
+
+    fn writer_builder(&self) -> csv::WriterBuilder {
+        let mut builder = csv::WriterBuilder::new();
+        builder
+            .delimiter(self.delimiter)
+            .quote_style(match self.quote_style {
+                QuoteStyle::Necessary => csv::QuoteStyle::Necessary,
+                QuoteStyle::Always => csv::QuoteStyle::Always,
+                QuoteStyle::NonNumeric => csv::QuoteStyle::NonNumeric,
+                QuoteStyle::Never => csv::QuoteStyle::Never,
+            })
+            .terminator(match self.terminator {
+                LineTerminator::Lf => csv::Terminator::Any(b'\n'),
+                LineTerminator::CrLf => csv::Terminator::CRLF,
+            });
+        builder
+    }
+
+    fn sanitize<'s>(&self, s: &'s str, row: usize, col: usize) -> Result<Cow<'s, str>, Error> {
+        if !is_formula_like(s) {
+            return Ok(Cow::Borrowed(s));
+        }
+        match self.injection {
+            InjectionPolicy::TabPrefix => Ok(Cow::Owned(format!("\t{s}"))),
+            InjectionPolicy::QuotePrefix => Ok(Cow::Owned(format!("'{s}"))),
+            InjectionPolicy::Reject => Err(Error::CsvInjection { row, col }),
+            InjectionPolicy::Off => Ok(Cow::Borrowed(s)),
+        }
+    }
+}
+
+/// The kind of row group a table row belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Section {
+    Head,
+    Body,
+    Foot,
+}
+
+/// A run of consecutive rows that came from the same `<thead>`, `<tbody>` or
+/// `<tfoot>` element.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowGroup {
+    pub section: Section,
+    /// Position of the group among the groups of the same section, so the
+    /// second `<tbody>` has `index == 1`.
+    pub index: usize,
+    /// Grid rows covered by the group.
+    pub rows: Range<usize>,
+}
+
+/// The extent of the cell covering a grid slot.
+///
+/// A cell with `rowspan` or `colspan` greater than one covers several slots.
+/// The slot at `(row, col)` is its origin; the others are continuations that
+/// hold a copy of the same value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CellSpan {
+    /// Row of the origin slot.
+    pub row: usize,
+    /// Column of the origin slot.
+    pub col: usize,
+    pub rowspan: usize,
+    pub colspan: usize,
+}
+
+/// A view of one occupied grid slot together with the cell covering it.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Cell<'t, T> {
+    pub row: usize,
+    pub col: usize,
+    pub value: &'t T,
+    pub span: CellSpan,
+    /// Whether the cell is a header cell (`<th>`).
+    pub header: bool,
+}
+
+impl<T> Clone for Cell<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Cell<'_, T> {}
+
+impl<T> Cell<'_, T> {
+    /// Returns `true` if this slot is where the cell is anchored.
+    pub fn is_origin(&self) -> bool {
+        self.span.row == self.row && self.span.col == self.col
+    }
+
+    /// Returns `true` if this slot is covered by a cell anchored elsewhere.
+    pub fn is_continuation(&self) -> bool {
+        !self.is_origin()
+    }
+}
+
+/// The span of a cell covering only the slot at `(row, col)`.
+fn single_span(row: usize, col: usize) -> CellSpan {
+    CellSpan {
+        row,
+        col,
+        rowspan: 1,
+        colspan: 1,
+    }
+}
+
+/// What to put in the slots a spanning cell covers besides its origin.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum SpanFill<T> {
+    /// Repeat the cell value, as [`extract_table_nodes_to_table`] does.
+    ///
+    /// [`extract_table_nodes_to_table`]: crate::extract_table_nodes_to_table
+    #[default]
+    Repeat,
+    /// Leave continuation slots empty.
+    Empty,
+    /// Put the given value in continuation slots.
+    Placeholder(T),
+}
+
+/// A `<col>` element.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ColumnDef {
+    /// Number of grid columns the element covers.
+    pub span: usize,
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+    /// The `width` attribute, as written.
+    pub width: Option<String>,
+    pub style: Option<String>,
+    /// Whether the element hides its columns, see
+    /// [`element_utils::is_hidden`].
+    ///
+    /// [`element_utils::is_hidden`]: crate::element_utils::is_hidden
+    pub hidden: bool,
+}
+
+/// A `<colgroup>` element and the `<col>` elements it contains.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ColumnGroup {
+    /// Number of grid columns the group covers: the sum of the spans of its
+    /// `<col>` children, or its own `span` attribute when it has none.
+    pub span: usize,
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+    pub width: Option<String>,
+    pub style: Option<String>,
+    pub hidden: bool,
+    pub cols: Vec<ColumnDef>,
+}
+
+/// Horizontal alignment of a table column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Alignment {
+    /// No alignment marker (`---` in Markdown).
+    #[default]
+    None,
+    Left,
+    Center,
+    Right,
+}
+
+/// Column-level attributes of one grid column, merged from the `<col>` and
+/// `<colgroup>` covering it.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ColumnInfo {
+    /// Classes of the `<colgroup>` followed by those of the `<col>`.
+    pub classes: Vec<String>,
+    /// The `<col>` width, falling back to the `<colgroup>` width.
+    pub width: Option<String>,
+    /// The `<col>` style, falling back to the `<colgroup>` style.
+    pub style: Option<String>,
+    /// Whether the `<col>` or its `<colgroup>` hides the column.
+    pub hidden: bool,
+}
+
+/// What the `<table>` element said about itself, beyond the cell grid.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TableInfo {
+    /// Whitespace-normalized text of the first `<caption>` child.
+    pub caption: Option<String>,
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+    pub summary: Option<String>,
+    /// The `aria-label` attribute.
+    pub aria_label: Option<String>,
+    /// `<colgroup>` elements in document order. `<col>` elements that are
+    /// direct children of `<table>`, as in XHTML, form a group of their own.
+    pub column_groups: Vec<ColumnGroup>,
+}
+
+impl TableInfo {
+    /// Expands the column groups into one [`ColumnInfo`] per grid column
+    /// they cover, starting at the first column.
+    pub fn column_infos(&self) -> Vec<ColumnInfo> {
+        let mut columns = vec![];
+        for group in &self.column_groups {
+            let group_info = ColumnInfo {
+                classes: group.classes.clone(),
+                width: group.width.clone(),
+                style: group.style.clone(),
+                hidden: group.hidden,
+            };
+            if group.cols.is_empty() {
+                columns.extend(std::iter::repeat_n(group_info, group.span));
+                continue;
+            }
+            for col in &group.cols {
+                let info = ColumnInfo {
+                    classes: [&group.classes[..], &col.classes[..]].concat(),
+                    width: col.width.clone().or_else(|| group_info.width.clone()),
+                    style: col.style.clone().or_else(|| group_info.style.clone()),
+                    hidden: group.hidden || col.hidden,
+                };
+                columns.extend(std::iter::repeat_n(info, col.span));
+            }
+        }
+        columns
     }
 }
 
 pub struct Table<T> {
     size: (usize, usize),
     cells: Vec<Option<T>>,
+    /// For each slot covered by a spanning cell, the one-based index of its
+    /// span in `spans`. Slots of single-slot cells have none.
+    slot_spans: Vec<Option<NonZeroUsize>>,
+    /// The span of every spanning cell, stored once per cell.
+    spans: Vec<CellSpan>,
+    headers: Vec<bool>,
+    row_groups: Vec<RowGroup>,
+    columns: Vec<ColumnInfo>,
+    info: TableInfo,
 }
 
 impl<T> Table<T> {
@@ -21,11 +353,40 @@ impl<T> Table<T> {
     /// Rows are zero-indexed from top; columns are zero-indexed from left.
     /// The row index must come before the column index.
     ///
+    /// Only this slot becomes a cell of its own. The other slots of a
+    /// spanning cell it was part of keep that cell and its span, which
+    /// [`Table::cell`] still reports for them; set them too, or use
+    /// [`Table::set_spanning`], to break up a merged cell.
+    ///
     /// # Panics
     ///
     /// Panics if `row` is greater than or equal to the table row count, or
     /// if `col` is greater than or equal to the table column count.
     pub fn set(&mut self, row: usize, col: usize, item: T) {
+        self.set_slot(row, col, item, single_span(row, col));
+        self.headers[row * self.size.1 + col] = false;
+    }
+
+    /// Marks the slot at `(row, col)` as a header (`<th>`) cell or not.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `(row, col)` is out of bounds.
+    pub fn set_header(&mut self, row: usize, col: usize, header: bool) {
+        assert!(
+            row < self.size.0 && col < self.size.1,
+            "slot ({row}, {col}) out of bounds for table of size {:?}",
+            self.size
+        );
+        self.headers[row * self.size.1 + col] = header;
+    }
+
+    /// Sets a single slot to `item`, recording that it is covered by `span`.
+    ///
+    /// Slots are expected to be set in row-major order, so the slots of a
+    /// spanning cell share the span recorded at its origin, or to their left
+    /// or above when the origin was overwritten.
+    pub(crate) fn set_slot(&mut self, row: usize, col: usize, item: T, span: CellSpan) {
         assert!(
             row < self.size.0,
             "row index {row} out of bounds for table with {} rows",
@@ -36,7 +397,65 @@ impl<T> Table<T> {
             "column index {col} out of bounds for table with {} columns",
             self.size.1
         );
+        let slot_span = self.span_index(row, col, span);
         self.cells[row * self.size.1 + col] = Some(item);
+        self.slot_spans[row * self.size.1 + col] = slot_span;
+    }
+
+    /// Returns the index of `span` for a slot at `(row, col)` it covers,
+    /// recording the span if no neighbouring slot shares it yet.
+    fn span_index(&mut self, row: usize, col: usize, span: CellSpan) -> Option<NonZeroUsize> {
+        if span == single_span(row, col) {
+            return None;
+        }
+        let neighbours = [
+            (span.row, span.col),
+            (row, col.wrapping_sub(1)),
+            (row.wrapping_sub(1), col),
+        ];
+        let shared = neighbours.into_iter().find_map(|(row, col)| {
+            if row >= self.size.0 || col >= self.size.1 {
+                return None;
+            }
+            let index = self.slot_spans[row * self.size.1 + col]?;
+            (self.spans[index.get() - 1] == span).then_some(index)
+        });
+        shared.or_else(|| {
+            self.spans.push(span);
+            NonZeroUsize::new(self.spans.len())
+        })
+    }
+
+    /// Returns the occupied slot at `(row, col)`, or `None` if it is empty or
+    /// out of bounds.
+    pub fn cell(&self, row: usize, col: usize) -> Option<Cell<'_, T>> {
+        if row >= self.size.0 || col >= self.size.1 {
+            return None;
+        }
+        let index = row * self.size.1 + col;
+        let value = self.cells[index].as_ref()?;
+        let span = match self.slot_spans[index] {
+            Some(span) => self.spans[span.get() - 1],
+            None => single_span(row, col),
+        };
+        Some(Cell {
+            row,
+            col,
+            value,
+            span,
+            header: self.headers[index],
+        })
+    }
+
+    /// Iterates over the occupied slots in row-major order.
+    pub fn cells(&self) -> impl Iterator<Item = Cell<'_, T>> {
+        let (rows, cols) = self.size;
+        (0..rows).flat_map(move |row| (0..cols).filter_map(move |col| self.cell(row, col)))
+    }
+
+    /// Returns the table size as `(rows, cols)`.
+    pub fn size(&self) -> (usize, usize) {
+        self.size
     }
 
     pub fn rows(&self) -> Vec<Vec<Option<&T>>> {
@@ -50,6 +469,65 @@ impl<T> Table<T> {
         }
         rows
     }
+
+    /// Returns the row groups in grid order.
+    ///
+    /// `<tfoot>` groups always come last, matching how browsers lay out a
+    /// table regardless of where the footer appears in the markup. Tables
+    /// built with [`Table::new`] have no row groups.
+    pub fn row_groups(&self) -> &[RowGroup] {
+        &self.row_groups
+    }
+
+    /// Returns the row group containing `row`, if any.
+    pub fn row_group(&self, row: usize) -> Option<&RowGroup> {
+        self.row_groups
+            .iter()
+            .find(|group| group.rows.contains(&row))
+    }
+
+    /// Returns the caption, attributes and column definitions of the source
+    /// `<table>` element. Tables built with [`Table::new`] have an empty
+    /// [`TableInfo`].
+    pub fn info(&self) -> &TableInfo {
+        &self.info
+    }
+
+    pub fn set_info(&mut self, info: TableInfo) {
+        self.info = info;
+    }
+
+    /// Returns the column-level attributes of grid column `col`, or `None`
+    /// if no `<col>` or `<colgroup>` covers it.
+    pub fn column(&self, col: usize) -> Option<&ColumnInfo> {
+        self.columns.get(col)
+    }
+
+    /// Returns the column-level attributes of the leading grid columns that
+    /// a `<col>` or `<colgroup>` covers.
+    pub fn columns(&self) -> &[ColumnInfo] {
+        &self.columns
+    }
+
+    /// Sets the column-level attributes, one entry per leading grid column.
+    pub fn set_columns(&mut self, columns: Vec<ColumnInfo>) {
+        self.columns = columns;
+    }
+
+    /// Appends a row group covering `rows`, numbering it after the existing
+    /// groups of the same section.
+    pub fn push_row_group(&mut self, section: Section, rows: Range<usize>) {
+        let index = self
+            .row_groups
+            .iter()
+            .filter(|group| group.section == section)
+            .count();
+        self.row_groups.push(RowGroup {
+            section,
+            index,
+            rows,
+        });
+    }
 }
 
 impl<T> Table<T>
@@ -64,9 +542,174 @@ where
         Self {
             size,
             cells: vec![None; size.0 * size.1],
+            slot_spans: vec![None; size.0 * size.1],
+            spans: vec![],
+            headers: vec![false; size.0 * size.1],
+            row_groups: vec![],
+            columns: vec![],
+            info: TableInfo::default(),
+        }
+    }
+
+    /// Places `item` in every slot covered by a `rowspan` × `colspan` cell
+    /// anchored at `(row, col)`, recording the span for each slot.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell does not fit inside the table.
+    pub fn set_spanning(
+        &mut self,
+        row: usize,
+        col: usize,
+        rowspan: usize,
+        colspan: usize,
+        item: T,
+    ) {
+        let span = CellSpan {
+            row,
+            col,
+            rowspan,
+            colspan,
+        };
+        for i in row..row + rowspan {
+            for j in col..col + colspan {
+                self.set_slot(i, j, item.clone(), span);
+            }
+        }
+    }
+
+    /// Returns a copy of the table with continuation slots of spanning cells
+    /// filled according to `fill`.
+    ///
+    /// ```
+    /// # use sxd_html_table::{extract_table_nodes_to_table, table::SpanFill};
+    /// let package = sxd_html::parse_html(
+    ///     r#"<table><tr><td colspan="2">a</td></tr><tr><td>b</td><td>c</td></tr></table>"#,
+    /// );
+    /// let tables = extract_table_nodes_to_table(package.as_document().root()).unwrap();
+    /// let table = tables[0].to_string_table();
+    /// assert_eq!(table.fill_spans(SpanFill::Empty).to_csv().unwrap(), "a,\nb,c\n");
+    /// let merged = SpanFill::Placeholder("(merged)".to_string());
+    /// assert_eq!(table.fill_spans(merged).to_csv().unwrap(), "a,(merged)\nb,c\n");
+    /// ```
+    pub fn fill_spans(&self, fill: SpanFill<T>) -> Self {
+        let mut table = Table::new(self.size);
+        table.row_groups.clone_from(&self.row_groups);
+        table.columns.clone_from(&self.columns);
+        table.info.clone_from(&self.info);
+        for cell in self.cells() {
+            let value = match (&fill, cell.is_origin()) {
+                (_, true) | (SpanFill::Repeat, false) => cell.value.clone(),
+                (SpanFill::Placeholder(placeholder), false) => placeholder.clone(),
+                (SpanFill::Empty, false) => continue,
+            };
+            table.set_slot(cell.row, cell.col, value, cell.span);
+            table.set_header(cell.row, cell.col, cell.header);
         }
+        table
     }
 
+    /// Returns a copy of the table keeping only the row groups for which
+    /// `keep` returns `true`.
+    ///
+    /// Rows outside every row group are kept. The retained groups are
+    /// renumbered so their ranges and indices describe the new table.
+    ///
+    /// ```
+    /// # use sxd_html_table::{extract_table_nodes_to_table, table::Section};
+    /// let package = sxd_html::parse_html(
+    ///     "<table><tr><td>1</td></tr><tfoot><tr><td>total</td></tr></tfoot></table>",
+    /// );
+    /// let tables = extract_table_nodes_to_table(package.as_document().root()).unwrap();
+    /// let body = tables[0].filter_row_groups(|group| group.section != Section::Foot);
+    /// assert_eq!(body.to_string_table().to_csv().unwrap(), "1\n");
+    /// ```
+    pub fn filter_row_groups(&self, keep: impl Fn(&RowGroup) -> bool) -> Self {
+        let kept_rows: Vec<usize> = (0..self.size.0)
+            .filter(|&row| self.row_group(row).is_none_or(&keep))
+            .collect();
+        let mut new_index = vec![None; self.size.0];
+        for (new_row, &row) in kept_rows.iter().enumerate() {
+            new_index[row] = Some(new_row);
+        }
+        let mut table = Table::new((kept_rows.len(), self.size.1));
+        for (new_row, &row) in kept_rows.iter().enumerate() {
+            for col in 0..self.size.1 {
+                let Some(cell) = self.cell(row, col) else {
+                    continue;
+                };
+                // Spans lose the rows that were dropped, and a cell whose
+                // origin row was dropped is re-anchored at its first kept row.
+                let covered = cell.span.row..cell.span.row + cell.span.rowspan;
+                let mut kept = covered.filter_map(|row| new_index.get(row).copied().flatten());
+                let span = CellSpan {
+                    row: kept.next().unwrap_or(new_row),
+                    rowspan: 1 + kept.count(),
+                    ..cell.span
+                };
+                table.set_slot(new_row, col, cell.value.clone(), span);
+                table.set_header(new_row, col, cell.header);
+            }
+        }
+        let mut start = 0;
+        for group in self.row_groups.iter().filter(|group| keep(group)) {
+            let len = group.rows.len();
+            table.push_row_group(group.section, start..start + len);
+            start += len;
+        }
+        table.columns.clone_from(&self.columns);
+        table.info.clone_from(&self.info);
+        table
+    }
+
+    /// Returns a copy of the table keeping only the columns for which `keep`
+    /// returns `true`. `keep` receives the column index and its attributes,
+    /// if any.
+    ///
+    /// Spanning cells shrink by the dropped columns, and a cell whose origin
+    /// column was dropped is re-anchored at its first kept column.
+    pub fn filter_columns(&self, keep: impl Fn(usize, Option<&ColumnInfo>) -> bool) -> Self {
+        let kept_cols: Vec<usize> = (0..self.size.1)
+            .filter(|&col| keep(col, self.column(col)))
+            .collect();
+        let mut new_index = vec![None; self.size.1];
+        for (new_col, &col) in kept_cols.iter().enumerate() {
+            new_index[col] = Some(new_col);
+        }
+        let mut table = Table::new((self.size.0, kept_cols.len()));
+        for row in 0..self.size.0 {
+            for (new_col, &col) in kept_cols.iter().enumerate() {
+                let Some(cell) = self.cell(row, col) else {
+                    continue;
+                };
+                let covered = cell.span.col..cell.span.col + cell.span.colspan;
+                let mut kept = covered.filter_map(|col| new_index.get(col).copied().flatten());
+                let span = CellSpan {
+                    col: kept.next().unwrap_or(new_col),
+                    colspan: 1 + kept.count(),
+                    ..cell.span
+                };
+                table.set_slot(row, new_col, cell.value.clone(), span);
+                table.set_header(row, new_col, cell.header);
+            }
+        }
+        table.row_groups.clone_from(&self.row_groups);
+        table.columns = kept_cols
+            .iter()
+            .map_while(|&col| self.column(col).cloned())
+            .collect();
+        table.info.clone_from(&self.info);
+        table
+    }
+
+    /// Returns a copy of the table without the columns hidden by their
+    /// `<col>` or `<colgroup>`, such as `style="visibility: collapse"`.
+    pub fn drop_hidden_columns(&self) -> Self {
+        self.filter_columns(|_, column| column.is_none_or(|column| !column.hidden))
+    }
+
+    /// Returns a copy of the table with every slot converted by `f`, which
+    /// receives the slot position and value.
     pub fn map<T2>(&self, f: impl Fn(usize, usize, &T) -> T2) -> Table<T2>
     where
         T2: Clone,
@@ -81,12 +724,17 @@ where
     S: Clone,
 {
     let mut new_table = Table::new(table.size);
-    for i in 0..table.size.0 {
-        for j in 0..table.size.1 {
-            if let Some(item) = &table.cells[i * table.size.1 + j] {
-                new_table.set(i, j, f(i, j, item));
-            }
-        }
+    new_table.row_groups.clone_from(&table.row_groups);
+    new_table.columns.clone_from(&table.columns);
+    new_table.info.clone_from(&table.info);
+    for cell in table.cells() {
+        new_table.set_slot(
+            cell.row,
+            cell.col,
+            f(cell.row, cell.col, cell.value),
+            cell.span,
+        );
+        new_table.set_header(cell.row, cell.col, cell.header);
     }
     new_table
 }
@@ -96,36 +744,59 @@ where
     T: std::fmt::Display,
 {
     pub fn write_csv(&self, writer: &mut impl std::io::Write) -> Result<(), Error> {
-        let mut buf: Vec<u8> = Vec::new();
-        {
-            let mut csv_writer = csv::Writer::from_writer(&mut buf);
-            for row in &self.rows() {
-                let mut record = csv::StringRecord::new();
-                for cell in row {
-                    if let Some(item) = cell {
-                        record.push_field(&sanitize_formula_injection(&item.to_string()));
-                    } else {
-                        record.push_field("");
-                    }
+        self.write_csv_with(writer, &CsvOptions::default())
+    }
+
+    /// Writes the table as CSV according to `options`.
+    ///
+    /// Records are streamed straight into `writer` without buffering the
+    /// whole table, so on error part of the output may already have been
+    /// written. Failures of `writer` are returned as [`Error::Io`].
+    pub fn write_csv_with(
+        &self,
+        writer: &mut impl std::io::Write,
+        options: &CsvOptions,
+    ) -> Result<(), Error> {
+        let skip = if options.header {
+            0
+        } else {
+            self.header_row_count(HeaderRows::Detect)
+        };
+        if options.bom {
+            writer.write_all("\u{feff}".as_bytes())?;
+        }
+        let mut csv_writer = options.writer_builder().from_writer(writer);
+        let mut record = csv::StringRecord::new();
+        for i in skip..self.size.0 {
+            record.clear();
+            for j in 0..self.size.1 {
+                if let Some(item) = &self.cells[i * self.size.1 + j] {
+                    record.push_field(&options.sanitize(&item.to_string(), i, j)?);
+                } else {
+                    record.push_field("");
                 }
-                csv_writer
-                    .write_record(&record)
-                    .map_err(|_| Error::FailedToConvertToCSV)?;
             }
-            csv_writer
-                .flush()
-                .map_err(|_| Error::FailedToConvertToCSV)?;
+            csv_writer.write_record(&record).map_err(csv_error)?;
         }
-        writer
-            .write_all(&buf)
-            .map_err(|_| Error::FailedToConvertToCSV)
+        csv_writer.flush()?;
+        Ok(())
     }
 
     pub fn to_csv(&self) -> Result<String, Error> {
-        let mut buf = std::io::BufWriter::new(Vec::new());
-        self.write_csv(&mut buf)?;
-        let bytes = buf.into_inner().map_err(|_| Error::FailedToConvertToCSV)?;
-        String::from_utf8(bytes).map_err(|_| Error::FailedToConvertToCSV)
+        self.to_csv_with(&CsvOptions::default())
+    }
+
+    pub fn to_csv_with(&self, options: &CsvOptions) -> Result<String, Error> {
+        let mut buf = Vec::new();
+        self.write_csv_with(&mut buf, options)?;
+        crate::utf8_output(buf)
+    }
+}
+
+fn csv_error(err: csv::Error) -> Error {
+    match err.into_kind() {
+        csv::ErrorKind::Io(err) => Error::Io(err),
+        _ => Error::FailedToConvertToCSV,
     }
 }
 
@@ -157,4 +828,153 @@ mod tests {
 
         table.set(0, 2, 42);
     }
+
+    #[test]
+    fn csv_options_control_dialect_and_header() {
+        let mut table = Table::new((2, 2));
+        table.set(0, 0, "name");
+        table.set(0, 1, "note");
+        table.set(1, 0, "a;b");
+        table.set(1, 1, "x");
+        table.set_header(0, 0, true);
+        table.set_header(0, 1, true);
+
+        let options = CsvOptions::new()
+            .delimiter(b';')
+            .terminator(LineTerminator::CrLf);
+        assert_eq!(
+            table.to_csv_with(&options).unwrap(),
+            "name;note\r\n\"a;b\";x\r\n"
+        );
+        let options = CsvOptions::new()
+            .header(false)
+            .quote_style(QuoteStyle::Always)
+            .bom(true);
+        assert_eq!(
+            table.to_csv_with(&options).unwrap(),
+            "\u{feff}\"a;b\",\"x\"\n"
+        );
+        let options = CsvOptions::new().delimiter(b'\t');
+        assert_eq!(table.to_csv_with(&options).unwrap(), "name\tnote\na;b\tx\n");
+    }
+
+    #[test]
+    fn write_csv_propagates_io_errors() {
+        struct FailingWriter;
+
+        impl std::io::Write for FailingWriter {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::BrokenPipe,
+                    "closed",
+                ))
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut table = Table::new((1, 1));
+        table.set(0, 0, "a");
+
+        match table.write_csv(&mut FailingWriter) {
+            Err(Error::Io(err)) => assert_eq!(err.kind(), std::io::ErrorKind::BrokenPipe),
+            other => panic!("expected Io error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn csv_injection_policies() {
+        let mut table = Table::new((1, 2));
+        table.set(0, 0, "-5");
+        table.set(0, 1, "=1+1");
+
+        let csv = |injection| table.to_csv_with(&CsvOptions::new().injection(injection));
+        assert_eq!(csv(InjectionPolicy::TabPrefix).unwrap(), "\t-5,\t=1+1\n");
+        assert_eq!(csv(InjectionPolicy::QuotePrefix).unwrap(), "'-5,'=1+1\n");
+        assert_eq!(csv(InjectionPolicy::Off).unwrap(), "-5,=1+1\n");
+        assert!(matches!(
+            csv(InjectionPolicy::Reject),
+            Err(Error::CsvInjection { row: 0, col: 0 })
+        ));
+    }
+
+    #[test]
+    fn cells_distinguish_origin_from_continuations() {
+        let mut table = Table::new((2, 3));
+        table.set_spanning(0, 0, 2, 2, "a");
+        table.set(0, 2, "b");
+
+        let origin = table.cell(0, 0).unwrap();
+        assert!(origin.is_origin());
+        assert_eq!(
+            origin.span,
+            CellSpan {
+                row: 0,
+                col: 0,
+                rowspan: 2,
+                colspan: 2,
+            }
+        );
+        assert!(table.cell(1, 1).unwrap().is_continuation());
+        assert!(table.cell(0, 2).unwrap().is_origin());
+        assert!(table.cell(1, 2).is_none());
+        assert_eq!(table.cells().filter(Cell::is_origin).count(), 2);
+        // The four slots of the spanning cell share one recorded span, and
+        // the single-slot cell records none.
+        assert_eq!(table.spans.len(), 1);
+
+        let empty = table.fill_spans(SpanFill::Empty);
+        assert_eq!(
+            empty.rows(),
+            [[Some(&"a"), None, Some(&"b")], [None, None, None]]
+        );
+        let placeholder = table.fill_spans(SpanFill::Placeholder("-"));
+        assert_eq!(placeholder.rows()[1], [Some(&"-"), Some(&"-"), None]);
+        assert_eq!(placeholder.cell(1, 0).unwrap().span.row, 0);
+    }
+
+    #[test]
+    fn filter_row_groups_reanchors_spans() {
+        let mut table = Table::new((3, 1));
+        table.set_spanning(0, 0, 3, 1, "a");
+        table.push_row_group(Section::Head, 0..1);
+        table.push_row_group(Section::Body, 1..3);
+
+        let body = table.filter_row_groups(|group| group.section == Section::Body);
+
+        let origin = body.cell(0, 0).unwrap();
+        assert!(origin.is_origin());
+        assert_eq!(origin.span.rowspan, 2);
+        assert!(body.cell(1, 0).unwrap().is_continuation());
+    }
+
+    #[test]
+    fn filter_row_groups_renumbers_kept_groups() {
+        let mut table = Table::new((4, 1));
+        for row in 0..4 {
+            table.set(row, 0, row);
+        }
+        table.push_row_group(Section::Body, 0..1);
+        table.push_row_group(Section::Body, 1..3);
+        table.push_row_group(Section::Foot, 3..4);
+
+        let second_body =
+            table.filter_row_groups(|group| group.section == Section::Body && group.index == 1);
+
+        assert_eq!(second_body.rows(), [[Some(&1)], [Some(&2)]]);
+        assert_eq!(
+            second_body.row_groups(),
+            [RowGroup {
+                section: Section::Body,
+                index: 0,
+                rows: 0..2,
+            }]
+        );
+        assert_eq!(
+            table.map(|_, _, v| *v).row_group(3).unwrap().section,
+            Section::Foot
+        );
+    }
 }
@@ -0,0 +1,389 @@
+use sxd_xpath::nodeset::Node;
+
+use crate::{element_utils, table::Table};
+
+/// Elements whose contents are never rendered as text. `<head>` only holds
+/// metadata such as `<title>`, and is reached when [`cell_text`] is given a
+/// whole document.
+const SKIPPED_ELEMENTS: &[&str] = &["script", "style", "template", "noscript", "head"];
+
+/// Elements that start and end on a line of their own.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "caption",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "tr",
+    "ul",
+];
+
+/// Elements separated from their neighbours by a space, like the cells of a
+/// nested table.
+const CELL_ELEMENTS: &[&str] = &["td", "th"];
+
+/// Elements whose whitespace is kept as written.
+const PREFORMATTED_ELEMENTS: &[&str] = &["pre", "textarea", "listing"];
+
+/// Options for [`cell_text`] and [`Table::to_text_table`].
+///
+/// The default collapses and trims whitespace except inside `<pre>`, turns
+/// `<br>` and block elements into line breaks, skips `<script>`, `<style>`
+/// and hidden elements, and uses the `alt` text of images.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextOptions {
+    collapse_whitespace: bool,
+    trim: bool,
+    preserve_pre: bool,
+    line_breaks: bool,
+    skip_hidden: bool,
+    image_alt: bool,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        Self {
+            collapse_whitespace: true,
+            trim: true,
+            preserve_pre: true,
+            line_breaks: true,
+            skip_hidden: true,
+            image_alt: true,
+        }
+    }
+}
+
+impl TextOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces every run of whitespace with a single space.
+    pub fn collapse_whitespace(mut self, collapse_whitespace: bool) -> Self {
+        self.collapse_whitespace = collapse_whitespace;
+        self
+    }
+
+    /// Removes leading and trailing whitespace from the cell text, apart
+    /// from whitespace kept by [`TextOptions::preserve_pre`].
+    pub fn trim(mut self, trim: bool) -> Self {
+        self.trim = trim;
+        self
+    }
+
+    /// Keeps the whitespace inside `<pre>`, `<textarea>` and `<listing>`
+    /// even when collapsing.
+    pub fn preserve_pre(mut self, preserve_pre: bool) -> Self {
+        self.preserve_pre = preserve_pre;
+        self
+    }
+
+    /// Writes `<br>` as a newline and puts block elements such as `<p>`,
+    /// `<div>` or `<li>` on lines of their own. When disabled, they separate
+    /// words like a space.
+    pub fn line_breaks(mut self, line_breaks: bool) -> Self {
+        self.line_breaks = line_breaks;
+        self
+    }
+
+    /// Skips elements hidden with the `hidden` attribute or an inline style,
+    /// see [`element_utils::is_hidden`]. `<script>`, `<style>`,
+    /// `<template>`, `<noscript>` and `<head>` are always skipped.
+    pub fn skip_hidden(mut self, skip_hidden: bool) -> Self {
+        self.skip_hidden = skip_hidden;
+        self
+    }
+
+    /// Writes the `alt` attribute of `<img>` elements in their place.
+    pub fn image_alt(mut self, image_alt: bool) -> Self {
+        self.image_alt = image_alt;
+        self
+    }
+}
+
+impl Table<Node<'_>> {
+    /// Converts every cell to its rendered text as described by `options`.
+    ///
+    /// Unlike [`Table::to_string_table`], which concatenates the raw text
+    /// nodes, this drops indentation, script contents and hidden elements
+    /// and keeps line breaks.
+    pub fn to_text_table(&self, options: &TextOptions) -> Table<String> {
+        self.map(|_, _, node| cell_text(*node, options))
+    }
+}
+
+/// Returns the text of `node` and its descendants as described by `options`.
+pub fn cell_text(node: Node<'_>, options: &TextOptions) -> String {
+    let mut writer = TextWriter {
+        options,
+        out: String::new(),
+        content_end: 0,
+        pending_space: false,
+        pending_newline: false,
+    };
+    writer.children(node, false);
+    if options.trim {
+        writer.out.truncate(writer.content_end);
+    }
+    writer.out
+}
+
+struct TextWriter<'o> {
+    options: &'o TextOptions,
+    out: String,
+    /// Length of `out` up to the end of its last non-whitespace or
+    /// preformatted text; whatever follows is trimmable.
+    content_end: usize,
+    pending_space: bool,
+    pending_newline: bool,
+}
+
+impl TextWriter<'_> {
+    fn children(&mut self, node: Node<'_>, pre: bool) {
+        for child in node.children() {
+            match child {
+                Node::Text(text) => self.text(text.text(), pre),
+                Node::Element(element) => self.element(child, element, pre),
+                _ => {}
+            }
+        }
+    }
+
+    fn element(&mut self, node: Node<'_>, element: sxd_document::dom::Element<'_>, pre: bool) {
+        let is = |names: &[&str]| {
+            names
+                .iter()
+                .any(|name| element_utils::is_html_element(element, name))
+        };
+        if is(SKIPPED_ELEMENTS) || (self.options.skip_hidden && element_utils::is_hidden(element)) {
+            return;
+        }
+        if is(&["br"]) {
+            if self.options.line_breaks {
+                self.newline();
+            } else {
+                self.pending_space = true;
+            }
+            return;
+        }
+        if is(&["img"]) {
+            if let Some(alt) = element
+                .attribute_value("alt")
+                .filter(|_| self.options.image_alt)
+            {
+                self.text(alt, false);
+            }
+            return;
+        }
+        let block = is(BLOCK_ELEMENTS);
+        let cell = is(CELL_ELEMENTS);
+        if block {
+            self.block_boundary();
+        }
+        if cell {
+            self.pending_space = true;
+        }
+        let pre = pre || (self.options.preserve_pre && is(PREFORMATTED_ELEMENTS));
+        self.children(node, pre);
+        if block {
+            self.block_boundary();
+        }
+        if cell {
+            self.pending_space = true;
+        }
+    }
+
+    fn text(&mut self, text: &str, pre: bool) {
+        if pre {
+            if !text.is_empty() {
+                self.flush();
+                self.out.push_str(text);
+                self.content_end = self.out.len();
+            }
+            return;
+        }
+        if !self.options.collapse_whitespace {
+            let text = if self.options.trim && self.content_end == 0 {
+                text.trim_start()
+            } else {
+                text
+            };
+            if !text.is_empty() {
+                self.flush();
+                self.out.push_str(text);
+                if !text.trim_end().is_empty() {
+                    self.content_end = self.out.len() - (text.len() - text.trim_end().len());
+                }
+            }
+            return;
+        }
+        for word in text.split_inclusive(char::is_whitespace) {
+            let trimmed = word.trim_end_matches(char::is_whitespace);
+            if trimmed.is_empty() {
+                self.pending_space = true;
+                continue;
+            }
+            self.flush();
+            self.out.push_str(trimmed);
+            self.content_end = self.out.len();
+            self.pending_space = trimmed.len() < word.len();
+        }
+    }
+
+    fn block_boundary(&mut self) {
+        if self.options.line_breaks {
+            self.pending_newline = true;
+        } else {
+            self.pending_space = true;
+        }
+    }
+
+    fn newline(&mut self) {
+        if self.options.trim && self.content_end == 0 {
+            self.pending_space = false;
+            self.pending_newline = false;
+            return;
+        }
+        if self.options.collapse_whitespace {
+            let end = self.out.trim_end_matches(' ').len();
+            self.out.truncate(end.max(self.content_end));
+        }
+        self.out.push('\n');
+        self.pending_space = false;
+        self.pending_newline = false;
+    }
+
+    /// Writes the separator owed before the next piece of text.
+    fn flush(&mut self) {
+        if self.pending_newline && !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.newline();
+        } else if self.pending_space
+            && !self.out.is_empty()
+            && !self.out.ends_with(char::is_whitespace)
+        {
+            self.out.push(' ');
+        }
+        self.pending_space = false;
+        self.pending_newline = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract_table_nodes_to_table;
+
+    const HTML: &str = r#"
+    <table>
+        <tr>
+            <td>
+                Total
+                <b>price</b><br>
+                (incl. tax)
+                <script>track()</script><style>td { color: red }</style>
+                <span hidden>internal</span><span style="display: none">x</span>
+            </td>
+            <td><p>First</p><p>Second</p><ul><li>one</li><li>two</li></ul></td>
+            <td><img src="flag.png" alt="Flag"> France</td>
+            <td><pre>  a   b
+  c</pre></td>
+        </tr>
+    </table>
+    "#;
+
+    fn texts(options: &TextOptions) -> Vec<String> {
+        let package = sxd_html::parse_html(HTML);
+        let document = package.as_document();
+        let tables = extract_table_nodes_to_table(document.root()).unwrap();
+        let table = tables[0].to_text_table(options);
+        table.rows()[0]
+            .iter()
+            .map(|cell| cell.unwrap().clone())
+            .collect()
+    }
+
+    #[test]
+    fn renders_cells_like_a_browser() {
+        assert_eq!(
+            texts(&TextOptions::new()),
+            [
+                "Total price\n(incl. tax)",
+                "First\nSecond\none\ntwo",
+                "Flag France",
+                "  a   b\n  c"
+            ]
+        );
+    }
+
+    #[test]
+    fn options_can_be_turned_off() {
+        let options = TextOptions::new()
+            .line_breaks(false)
+            .image_alt(false)
+            .preserve_pre(false)
+            .skip_hidden(false);
+        assert_eq!(
+            texts(&options),
+            [
+                "Total price (incl. tax) internalx",
+                "First Second one two",
+                "France",
+                "a b c"
+            ]
+        );
+
+        let raw = texts(&TextOptions::new().collapse_whitespace(false).trim(false));
+        assert!(raw[0].starts_with("\n                Total\n"));
+        assert!(!raw[0].contains("track()"));
+    }
+
+    #[test]
+    fn trims_only_whitespace_outside_preformatted_text() {
+        let package =
+            sxd_html::parse_html("<table><tr><td> <br> x <pre> a \n</pre> <br> </td></tr></table>");
+        let tables = extract_table_nodes_to_table(package.as_document().root()).unwrap();
+        let cell = *tables[0].cell(0, 0).unwrap().value;
+        assert_eq!(cell_text(cell, &TextOptions::new()), "x\n a \n");
+        let options = TextOptions::new().collapse_whitespace(false);
+        assert_eq!(cell_text(cell, &options), "x \n a \n");
+    }
+
+    #[test]
+    fn separates_the_cells_of_nested_tables() {
+        let package = sxd_html::parse_html(
+            "<table><tr><td><table><tr><td>a</td><th>b</th></tr><tr><td>c</td><td>d</td></tr></table></td></tr></table>",
+        );
+        let tables = extract_table_nodes_to_table(package.as_document().root()).unwrap();
+        let cell = *tables[0].cell(0, 0).unwrap().value;
+        assert_eq!(cell_text(cell, &TextOptions::new()), "a b\nc d");
+        let options = TextOptions::new().line_breaks(false);
+        assert_eq!(cell_text(cell, &options), "a b c d");
+    }
+}
@@ -0,0 +1,272 @@
+use sxd_xpath::nodeset::Node;
+
+use crate::{
+    extract::ExtractOptions,
+    node_utils::{child_table_nodes, form_table, limited_table_nodes},
+    table::Table,
+    Error,
+};
+
+/// A table together with the tables nested inside its cells.
+pub struct TableTree<'a> {
+    pub table: Table<Node<'a>>,
+    /// Tables found in the cells of `table`, in row-major cell order and then
+    /// document order within a cell.
+    pub children: Vec<NestedTable<'a>>,
+}
+
+/// A table nested in the cell anchored at `(row, col)` of its parent.
+pub struct NestedTable<'a> {
+    pub row: usize,
+    pub col: usize,
+    pub tree: TableTree<'a>,
+}
+
+/// One level of nesting: the `index`-th table inside the cell anchored at
+/// `(row, col)` of the enclosing table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PathStep {
+    pub row: usize,
+    pub col: usize,
+    pub index: usize,
+}
+
+/// Where a table sits in the nesting tree of a document.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TablePath {
+    /// Index of the top-level table the path starts from.
+    pub root: usize,
+    pub steps: Vec<PathStep>,
+}
+
+impl TablePath {
+    /// Returns how deeply the table is nested; top-level tables have depth 0.
+    pub fn depth(&self) -> usize {
+        self.steps.len()
+    }
+}
+
+impl<'a> TableTree<'a> {
+    /// Returns the tables nested in the cell anchored at `(row, col)`.
+    pub fn children_at(&self, row: usize, col: usize) -> impl Iterator<Item = &TableTree<'a>> {
+        self.children
+            .iter()
+            .filter(move |child| child.row == row && child.col == col)
+            .map(|child| &child.tree)
+    }
+
+    /// Flattens the tree in pre-order, pairing every table with its path.
+    pub fn flatten(self, root: usize) -> Vec<(TablePath, Table<Node<'a>>)> {
+        let mut tables = vec![];
+        let mut stack = vec![(
+            self,
+            TablePath {
+                root,
+                steps: vec![],
+            },
+        )];
+        while let Some((tree, path)) = stack.pop() {
+            let mut counts: Vec<((usize, usize), usize)> = vec![];
+            let mut children = vec![];
+            for child in tree.children {
+                let position = (child.row, child.col);
+                let index = match counts.iter_mut().find(|(seen, _)| *seen == position) {
+                    Some((_, count)) => {
+                        *count += 1;
+                        *count - 1
+                    }
+                    None => {
+                        counts.push((position, 1));
+                        0
+                    }
+                };
+                let mut child_path = path.clone();
+                child_path.steps.push(PathStep {
+                    row: child.row,
+                    col: child.col,
+                    index,
+                });
+                children.push((child.tree, child_path));
+            }
+            tables.push((path, tree.table));
+            stack.extend(children.into_iter().rev());
+        }
+        tables
+    }
+}
+
+/// Extracts the top-level tables of a document along with every table
+/// nested in their cells.
+///
+/// Unlike [`extract_table_nodes_to_table`], inner tables are parsed too and
+/// attached to the cell containing them. Each nested table is reported once,
+/// at the origin slot of a spanning cell.
+///
+/// [`extract_table_nodes_to_table`]: crate::extract_table_nodes_to_table
+pub fn extract_table_tree<'a>(node: impl Into<Node<'a>>) -> Result<Vec<TableTree<'a>>, Error> {
+    extract_table_tree_with(node, &ExtractOptions::default())
+}
+
+/// Like [`extract_table_tree`], applying the limits of `options` to every
+/// table, nested or not. The table limit only counts top-level tables.
+pub fn extract_table_tree_with<'a>(
+    node: impl Into<Node<'a>>,
+    options: &ExtractOptions,
+) -> Result<Vec<TableTree<'a>>, Error> {
+    let mut trees = vec![];
+    for (index, node) in limited_table_nodes(node, options)?.into_iter().enumerate() {
+        if let Some(tree) = table_tree(node, options).map_err(|err| err.in_table(index))? {
+            trees.push(tree);
+        }
+    }
+    Ok(trees)
+}
+
+/// Extracts every table of a document, nested or not, paired with its path
+/// in the nesting tree. Tables are listed in pre-order.
+pub fn extract_all_tables<'a>(
+    node: impl Into<Node<'a>>,
+) -> Result<Vec<(TablePath, Table<Node<'a>>)>, Error> {
+    extract_all_tables_with(node, &ExtractOptions::default())
+}
+
+/// Like [`extract_all_tables`], applying the limits of `options` as
+/// [`extract_table_tree_with`] does.
+pub fn extract_all_tables_with<'a>(
+    node: impl Into<Node<'a>>,
+    options: &ExtractOptions,
+) -> Result<Vec<(TablePath, Table<Node<'a>>)>, Error> {
+    let mut tables = vec![];
+    for (root, tree) in extract_table_tree_with(node, options)?
+        .into_iter()
+        .enumerate()
+    {
+        tables.extend(tree.flatten(root));
+    }
+    Ok(tables)
+}
+
+/// Builds the tree of `node`, or `None` when the table is skipped for
+/// exceeding a limit. Skipped nested tables are left out of their parent.
+fn table_tree<'a>(
+    node: Node<'a>,
+    options: &ExtractOptions,
+) -> Result<Option<TableTree<'a>>, Error> {
+    // Tables in pre-order, each with its parent's index in `formed` and the
+    // cell of the parent it sits in.
+    let mut formed = vec![];
+    let mut stack = vec![(node, None)];
+    while let Some((node, parent)) = stack.pop() {
+        let Some(table) = form_table(node, options)? else {
+            continue;
+        };
+        let index = formed.len();
+        let mut inner = vec![];
+        for cell in table.cells().filter(|cell| cell.is_origin()) {
+            for node in child_table_nodes(*cell.value) {
+                inner.push((node, Some((index, cell.row, cell.col))));
+            }
+        }
+        stack.extend(inner.into_iter().rev());
+        formed.push((table, parent));
+    }
+
+    // Children come after their parent, so attaching them from the back
+    // completes every subtree before it is moved into its parent.
+    let mut children: Vec<Vec<NestedTable<'a>>> = formed.iter().map(|_| vec![]).collect();
+    while let Some((table, parent)) = formed.pop() {
+        let mut nested = children.pop().unwrap_or_default();
+        nested.reverse();
+        let tree = TableTree {
+            table,
+            children: nested,
+        };
+        match parent {
+            Some((index, row, col)) => children[index].push(NestedTable { row, col, tree }),
+            None => return Ok(Some(tree)),
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HTML: &str = r#"
+    <html>
+        <body>
+            <table>
+                <tr>
+                    <td colspan="2">
+                        <div><table><tr><td>inner 1</td></tr></table></div>
+                        <table>
+                            <tr><td><table><tr><td>deep</td></tr></table></td></tr>
+                        </table>
+                    </td>
+                </tr>
+                <tr><td>a</td><td><table><tr><td>inner 2</td></tr></table></td></tr>
+            </table>
+            <table><tr><td>second</td></tr></table>
+        </body>
+    </html>
+    "#;
+
+    #[test]
+    fn builds_tree_of_nested_tables() {
+        let package = sxd_html::parse_html(HTML);
+        let document = package.as_document();
+        let trees = extract_table_tree(document.root()).unwrap();
+
+        assert_eq!(trees.len(), 2);
+        let outer = &trees[0];
+        assert_eq!(outer.children.len(), 3);
+        let first_cell: Vec<_> = outer.children_at(0, 0).collect();
+        assert_eq!(first_cell.len(), 2);
+        assert_eq!(
+            first_cell[0].table.to_string_table().to_csv().unwrap(),
+            "inner 1\n"
+        );
+        assert_eq!(
+            first_cell[1].children[0]
+                .tree
+                .table
+                .to_string_table()
+                .to_csv()
+                .unwrap(),
+            "deep\n"
+        );
+        assert_eq!(outer.children_at(0, 1).count(), 0);
+        assert_eq!(outer.children_at(1, 1).count(), 1);
+    }
+
+    #[test]
+    fn extracts_all_tables_with_paths() {
+        let package = sxd_html::parse_html(HTML);
+        let document = package.as_document();
+        let tables = extract_all_tables(document.root()).unwrap();
+
+        let step = |row, col, index| PathStep { row, col, index };
+        let first_text = |table: &Table<Node<'_>>| {
+            table.to_string_table().rows()[0][0]
+                .unwrap()
+                .trim()
+                .to_string()
+        };
+        assert_eq!(tables.len(), 6);
+        assert_eq!(tables[1].0.steps, [step(0, 0, 0)]);
+        assert_eq!(first_text(&tables[1].1), "inner 1");
+        assert_eq!(tables[3].0.steps, [step(0, 0, 1), step(0, 0, 0)]);
+        assert_eq!(first_text(&tables[3].1), "deep");
+        assert_eq!(tables[4].0.steps, [step(1, 1, 0)]);
+        assert_eq!(first_text(&tables[4].1), "inner 2");
+        assert_eq!(
+            tables[5].0,
+            TablePath {
+                root: 1,
+                steps: vec![],
+            }
+        );
+        assert_eq!(tables[3].0.depth(), 2);
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+
+use sxd_xpath::{nodeset::Node, Context, Factory, Value, XPath};
+
+use crate::{
+    table::{CellSpan, Table},
+    Error,
+};
+
+/// Compiles `expr`, failing with [`sxd_xpath::Error::NoXPath`] if it is
+/// empty.
+pub fn compile_xpath(expr: &str) -> Result<XPath, Error> {
+    let expression = Factory::new().build(expr).map_err(sxd_xpath::Error::from)?;
+    Ok(expression.ok_or(sxd_xpath::Error::NoXPath)?)
+}
+
+/// Evaluates XPath expressions in a reusable [`Context`], compiling each
+/// distinct expression only once.
+///
+/// ```
+/// use sxd_html_table::xpath::XPathEvaluator;
+///
+/// let package = sxd_html::parse_html("<table><tr><td>1</td><td>2</td></tr></table>");
+/// let root = package.as_document().root();
+/// let mut evaluator = XPathEvaluator::new();
+/// evaluator.context_mut().set_variable("n", 2.0);
+/// for _ in 0..3 {
+///     let cell = evaluator.evaluate(root, "string(//td[$n])").unwrap();
+///     assert_eq!(cell.string(), "2");
+/// }
+/// assert_eq!(evaluator.cached_len(), 1);
+/// ```
+pub struct XPathEvaluator<'d> {
+    factory: Factory,
+    context: Context<'d>,
+    cache: HashMap<String, XPath>,
+}
+
+impl Default for XPathEvaluator<'_> {
+    fn default() -> Self {
+        Self::with_context(Context::new())
+    }
+}
+
+impl<'d> XPathEvaluator<'d> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an evaluator using `context` for its namespace, variable and
+    /// function bindings.
+    pub fn with_context(context: Context<'d>) -> Self {
+        Self {
+            factory: Factory::new(),
+            context,
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn context(&self) -> &Context<'d> {
+        &self.context
+    }
+
+    /// Returns the context so bindings can be added or changed. Compiled
+    /// expressions stay valid, since bindings are resolved at evaluation.
+    pub fn context_mut(&mut self) -> &mut Context<'d> {
+        &mut self.context
+    }
+
+    /// Returns the compiled form of `expr`, compiling it on first use.
+    pub fn compile(&mut self, expr: &str) -> Result<&XPath, Error> {
+        if !self.cache.contains_key(expr) {
+            let expression = self
+                .factory
+                .build(expr)
+                .map_err(sxd_xpath::Error::from)?
+                .ok_or(sxd_xpath::Error::NoXPath)?;
+            self.cache.insert(expr.to_string(), expression);
+        }
+        Ok(&self.cache[expr])
+    }
+
+    /// Evaluates `expr` with `node` as the context node.
+    pub fn evaluate(&mut self, node: impl Into<Node<'d>>, expr: &str) -> Result<Value<'d>, Error> {
+        self.compile(expr)?;
+        let xpath = &self.cache[expr];
+        Ok(xpath
+            .evaluate(&self.context, node)
+            .map_err(sxd_xpath::Error::from)?)
+    }
+
+    /// Returns the number of compiled expressions in the cache.
+    pub fn cached_len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+    }
+}
+
+impl<'a> Table<Node<'a>> {
+    /// Evaluates `xpath` relative to every cell, compiling it once.
+    ///
+    /// The expression is evaluated once per cell and the result is shared by
+    /// all the slots the cell spans.
+    ///
+    /// ```
+    /// use sxd_html_table::extract_table_nodes_to_table;
+    ///
+    /// let package = sxd_html::parse_html(
+    ///     r#"<table><tr><td><a href="/a">A</a></td><td>none</td></tr></table>"#,
+    /// );
+    /// let tables = extract_table_nodes_to_table(package.as_document().root()).unwrap();
+    /// let hrefs = tables[0].select("string(.//a/@href)").unwrap().strings();
+    /// assert_eq!(hrefs.to_csv().unwrap(), "/a,\n");
+    /// ```
+    pub fn select(&self, xpath: &str) -> Result<Table<Value<'a>>, Error> {
+        self.select_with(&compile_xpath(xpath)?, &Context::new())
+    }
+
+    /// Evaluates `xpath` relative to every cell with `evaluator`, reusing its
+    /// bindings and compiled expressions.
+    pub fn select_in(
+        &self,
+        evaluator: &mut XPathEvaluator<'a>,
+        xpath: &str,
+    ) -> Result<Table<Value<'a>>, Error> {
+        evaluator.compile(xpath)?;
+        self.select_with(&evaluator.cache[xpath], &evaluator.context)
+    }
+
+    /// Evaluates a compiled expression relative to every cell in `context`,
+    /// which carries the namespace, variable and function bindings and can
+    /// be reused across tables.
+    pub fn select_with(
+        &self,
+        xpath: &XPath,
+        context: &Context<'a>,
+    ) -> Result<Table<Value<'a>>, Error> {
+        let mut by_origin: HashMap<CellSpan, Value<'a>> = HashMap::new();
+        let mut values = HashMap::new();
+        for cell in self.cells() {
+            let value = match by_origin.get(&cell.span) {
+                Some(value) => value.clone(),
+                None => {
+                    let value = xpath
+                        .evaluate(context, *cell.value)
+                        .map_err(sxd_xpath::Error::from)?;
+                    by_origin.insert(cell.span, value.clone());
+                    value
+                }
+            };
+            values.insert((cell.row, cell.col), value);
+        }
+        Ok(self.map(|row, col, _| values[&(row, col)].clone()))
+    }
+}
+
+impl<'a> Table<Value<'a>> {
+    /// Converts every value to a string with the XPath `string()` rules.
+    pub fn strings(&self) -> Table<String> {
+        self.map(|_, _, value| value.string())
+    }
+
+    /// Returns the selected nodes of every cell in document order. Values
+    /// that are not node-sets become empty lists.
+    pub fn nodes(&self) -> Table<Vec<Node<'a>>> {
+        self.map(|_, _, value| match value {
+            Value::Nodeset(nodes) => nodes.document_order(),
+            _ => vec![],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract_table_nodes_to_table;
+
+    const HTML: &str = r#"
+    <table>
+        <tr><td colspan="2"><a href="/x">x</a><a href="/y">y</a></td></tr>
+        <tr><td class="price">10</td><td>none</td></tr>
+    </table>
+    "#;
+
+    #[test]
+    fn projects_values_strings_and_nodes() {
+        let package = sxd_html::parse_html(HTML);
+        let document = package.as_document();
+        let tables = extract_table_nodes_to_table(document.root()).unwrap();
+
+        let counts = tables[0].select("count(.//a)").unwrap();
+        assert_eq!(counts.cell(0, 1).unwrap().value, &Value::Number(2.0));
+        assert!(counts.cell(0, 1).unwrap().is_continuation());
+        assert_eq!(counts.strings().to_csv().unwrap(), "2,2\n0,0\n");
+
+        let links = tables[0].select(".//a").unwrap().nodes();
+        let texts: Vec<String> = links
+            .cell(0, 0)
+            .unwrap()
+            .value
+            .iter()
+            .map(Node::string_value)
+            .collect();
+        assert_eq!(texts, ["x", "y"]);
+        assert!(links.cell(1, 0).unwrap().value.is_empty());
+    }
+
+    #[test]
+    fn evaluates_with_variables_and_reports_errors() {
+        let package = sxd_html::parse_html(HTML);
+        let document = package.as_document();
+        let tables = extract_table_nodes_to_table(document.root()).unwrap();
+
+        let xpath = compile_xpath("@class = $class").unwrap();
+        let mut context = Context::new();
+        context.set_variable("class", "price");
+        let matches = tables[0].select_with(&xpath, &context).unwrap();
+        assert_eq!(
+            matches.strings().to_csv().unwrap(),
+            "false,false\ntrue,false\n"
+        );
+
+        let mut evaluator = XPathEvaluator::with_context(context);
+        let first = tables[0]
+            .select_in(&mut evaluator, "@class = $class")
+            .unwrap();
+        evaluator.context_mut().set_variable("class", "other");
+        let second = tables[0]
+            .select_in(&mut evaluator, "@class = $class")
+            .unwrap();
+        assert_eq!(
+            first.strings().to_csv().unwrap(),
+            "false,false\ntrue,false\n"
+        );
+        assert_eq!(
+            second.strings().to_csv().unwrap(),
+            "false,false\nfalse,false\n"
+        );
+        assert_eq!(evaluator.cached_len(), 1);
+        assert!(evaluator.evaluate(document.root(), "(").is_err());
+        assert_eq!(evaluator.cached_len(), 1);
+
+        assert!(matches!(
+            tables[0].select("$missing"),
+            Err(Error::XPathEvaluationError(_))
+        ));
+        assert!(matches!(
+            tables[0].select(""),
+            Err(Error::XPathEvaluationError(_))
+        ));
+    }
+}
@@ -0,0 +1,83 @@
+//! Conformance corpus for the HTML "forming a table" algorithm.
+//!
+//! Every `tests/forming/NAME.html` holds one table, and `NAME.grid` the grid
+//! it must produce: one line per row with the trimmed text of the cell
+//! covering each slot (`.` for an empty slot), then a `groups:` line listing
+//! the row groups. Lines starting with `#` are comments.
+//!
+//! Rowspans are not clamped to their row group, which browsers do by
+//! default but the algorithm does not.
+
+use std::{fs, path::Path};
+
+use sxd_html_table::{extract_table_nodes_to_table_with, table::Section, ExtractOptions, Table};
+
+fn render(table: &Table<String>) -> String {
+    let mut lines: Vec<String> = table
+        .rows()
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|slot| slot.map_or(".", |text| text.trim()))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect();
+    let groups: Vec<String> = table
+        .row_groups()
+        .iter()
+        .map(|group| {
+            let section = match group.section {
+                Section::Head => "head",
+                Section::Body => "body",
+                Section::Foot => "foot",
+            };
+            format!("{section} {}..{}", group.rows.start, group.rows.end)
+        })
+        .collect();
+    lines.push(format!("groups: {}", groups.join(", ")));
+    lines.join("\n")
+}
+
+fn expected(grid: &str) -> String {
+    grid.lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn corpus_matches_expected_grids() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/forming");
+    let mut html_files: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "html"))
+        .collect();
+    html_files.sort();
+    assert!(
+        !html_files.is_empty(),
+        "no corpus files in {}",
+        dir.display()
+    );
+
+    let mut failures = vec![];
+    for html_path in &html_files {
+        let html = fs::read_to_string(html_path).unwrap();
+        let grid = fs::read_to_string(html_path.with_extension("grid")).unwrap();
+        let package = sxd_html::parse_html(&html);
+        let document = package.as_document();
+        let options = ExtractOptions::new().clamp_rowspans(false);
+        let tables = extract_table_nodes_to_table_with(document.root(), &options).unwrap();
+        let actual = render(&tables[0].to_string_table());
+        let expected = expected(&grid);
+        if actual != expected {
+            failures.push(format!(
+                "{}:\n--- expected\n{expected}\n--- actual\n{actual}",
+                html_path.display()
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+}